@@ -0,0 +1,168 @@
+//! Reading archives into trees of files that are compared like directories.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsString,
+    fs::File,
+    io::{self, BufReader, Read, Seek},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+use crate::dir::{Kind, Tree};
+
+/// An entry of an archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Member {
+    /// A file and its content.
+    File(Vec<u8>),
+    /// A directory, either stored or implied by the paths of other members.
+    Dir,
+    /// A symbolic link and its target.
+    Link(PathBuf),
+}
+
+/// The members of an archive by their path.
+///
+/// Symbolic links are never followed inside an archive: they are compared
+/// to other links by their targets.
+#[derive(Clone, Debug, Default)]
+pub struct Archive {
+    members: BTreeMap<PathBuf, Member>,
+}
+
+/// Returns `path` without leading `./` and other components that don't name
+/// a member, or `None` if nothing is left.
+fn member_path(path: &Path) -> Option<PathBuf> {
+    let path = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect::<PathBuf>();
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+impl Archive {
+    /// Returns whether `path` names an archive that [`Archive::open`]
+    /// reads, by its extension.
+    pub fn is_archive(path: &Path) -> bool {
+        let name = path.to_string_lossy().to_lowercase();
+        [".tar", ".tar.gz", ".tgz", ".zip", ".jar"]
+            .iter()
+            .any(|extension| name.ends_with(extension))
+    }
+
+    /// Reads the archive at `path`: a zip archive if its name ends with
+    /// `.zip` or `.jar`, and otherwise a tar archive that is decompressed
+    /// first if its name ends with `.gz` or `.tgz`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let name = path.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") || name.ends_with(".jar") {
+            Archive::read_zip(file)
+        } else if name.ends_with(".gz") || name.ends_with(".tgz") {
+            Archive::read_tar(GzDecoder::new(file))
+        } else {
+            Archive::read_tar(file)
+        }
+    }
+
+    /// Reads the members of the tar archive `reader`.
+    pub fn read_tar<R: Read>(reader: R) -> io::Result<Self> {
+        let mut archive = Archive::default();
+        for entry in tar::Archive::new(reader).entries()? {
+            let mut entry = entry?;
+            let Some(path) = member_path(&entry.path()?) else {
+                continue;
+            };
+            let member = match entry.header().entry_type() {
+                tar::EntryType::Directory => Member::Dir,
+                tar::EntryType::Symlink => match entry.link_name()? {
+                    Some(target) => Member::Link(target.into_owned()),
+                    None => continue,
+                },
+                kind if kind.is_file() => {
+                    let mut bytes = vec![];
+                    entry.read_to_end(&mut bytes)?;
+                    Member::File(bytes)
+                }
+                _ => continue,
+            };
+            archive.insert(path, member);
+        }
+        Ok(archive)
+    }
+
+    /// Reads the members of the zip archive `reader`. Members whose path
+    /// leaves the archive, such as `../file`, are skipped.
+    pub fn read_zip<R: Read + Seek>(reader: R) -> io::Result<Self> {
+        let mut zip = zip::ZipArchive::new(reader)?;
+        let mut archive = Archive::default();
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            let Some(path) = file.enclosed_name().as_deref().and_then(member_path) else {
+                continue;
+            };
+            let mut bytes = vec![];
+            let member = match file.is_dir() {
+                true => Member::Dir,
+                false => {
+                    file.read_to_end(&mut bytes)?;
+                    match file.is_symlink() {
+                        true => Member::Link(PathBuf::from(
+                            String::from_utf8_lossy(&bytes).into_owned(),
+                        )),
+                        false => Member::File(bytes),
+                    }
+                }
+            };
+            archive.insert(path, member);
+        }
+        Ok(archive)
+    }
+
+    /// Adds `member` at `path`, along with the directories that contain it.
+    pub fn insert(&mut self, path: PathBuf, member: Member) {
+        for dir in path.ancestors().skip(1) {
+            if !dir.as_os_str().is_empty() {
+                self.members.insert(dir.to_path_buf(), Member::Dir);
+            }
+        }
+        self.members.insert(path, member);
+    }
+
+    /// Returns the member at `path`, if there is one.
+    pub fn member(&self, path: &Path) -> Option<&Member> {
+        self.members.get(path)
+    }
+}
+
+impl Tree for Archive {
+    fn names(&self, rel: &Path) -> io::Result<BTreeSet<OsString>> {
+        if !rel.as_os_str().is_empty() && self.member(rel) != Some(&Member::Dir) {
+            let message = format!("{}: not a directory in the archive", rel.display());
+            return Err(io::Error::new(io::ErrorKind::NotFound, message));
+        }
+        Ok(self
+            .members
+            .keys()
+            .filter(|path| path.parent() == Some(rel))
+            .filter_map(|path| path.file_name().map(OsString::from))
+            .collect())
+    }
+
+    fn kind(&self, rel: &Path, _: bool) -> Kind {
+        match self.member(rel) {
+            Some(Member::Dir) => Kind::Dir,
+            Some(Member::Link(_)) => Kind::Link,
+            Some(Member::File(_)) | None => Kind::File,
+        }
+    }
+
+    fn canonical(&self, rel: &Path) -> PathBuf {
+        rel.to_path_buf()
+    }
+}
@@ -0,0 +1,57 @@
+//! Levenshtein distance restricted to a diagonal band, for when only small
+//! distances matter.
+
+use crate::Costs;
+
+/// Returns the least total cost under `costs` of an edit script of `s1` and
+/// `s2`, or `None` when it is larger than `max`.
+///
+/// Only the cells within `max` insertions or deletions of the diagonal are
+/// computed, since no edit script within the bound leaves them, so with unit
+/// costs this takes O(n * max) time and O(max) memory, and gives up as soon
+/// as a whole row exceeds the bound.
+pub(crate) fn distance<T: Eq>(s1: &[T], s2: &[T], max: usize, costs: &Costs) -> Option<usize> {
+    let (n, m) = (s1.len(), s2.len());
+    let reach = match costs.insert.min(costs.delete) {
+        0 => n.max(m),
+        cost => (max / cost).min(n.max(m)),
+    };
+    if n.abs_diff(m) > reach {
+        return None;
+    }
+    let width = 2 * reach + 1;
+    let over = max.saturating_add(1);
+    // `row[d]` holds the cell of column `i + d - reach` of the current row `i`.
+    let column = |i: usize, d: usize| (i + d).checked_sub(reach).filter(|&j| j <= m);
+    let mut prev = (0..width)
+        .map(|d| column(0, d).map_or(over, |j| (j * costs.insert).min(over)))
+        .collect::<Vec<_>>();
+    let mut cur = vec![over; width];
+    for i in 1..=n {
+        for d in 0..width {
+            let Some(j) = column(i, d) else {
+                cur[d] = over;
+                continue;
+            };
+            let mut cost = match j {
+                0 => i * costs.delete,
+                _ => match s1[i - 1] == s2[j - 1] {
+                    true => prev[d],
+                    false => prev[d] + costs.substitute,
+                },
+            };
+            if d > 0 && j > 0 {
+                cost = cost.min(cur[d - 1] + costs.insert);
+            }
+            if d + 1 < width {
+                cost = cost.min(prev[d + 1] + costs.delete);
+            }
+            cur[d] = cost.min(over);
+        }
+        if cur.iter().all(|&cost| cost > max) {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    Some(prev[m + reach - n]).filter(|&cost| cost <= max)
+}
@@ -0,0 +1,132 @@
+//! Defaults for the command line from configuration files.
+//!
+//! Every key of a file is the long name of one of the [`SETTINGS`], such as
+//! `format = "unified"`, `context = 5` or `ignore-blank-lines = true`, and a
+//! list gives a flag that can be repeated once per item. A setting of the
+//! project file replaces that of the user file, and the settings become
+//! arguments in front of those of the command line, except for the flags
+//! that the command line gives itself.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, ArgMatches, Command};
+
+use crate::error::Error;
+use toml::{Table, Value};
+
+/// The name of the per-project configuration file, looked for in the
+/// current directory and its ancestors.
+const PROJECT_FILE: &str = ".lev-diff.toml";
+
+/// The flags that can be set by a configuration file: those of the look of
+/// the output and of what is compared, but none that writes files or runs
+/// commands, since the project file comes with the files being compared.
+const SETTINGS: &[&str] = &[
+    "color",
+    "context",
+    "exclude",
+    "format",
+    "ignore-blank-lines",
+    "ignore-case",
+    "ignore-matching-lines",
+    "palette",
+    "theme",
+];
+
+/// Returns the configuration files that apply, the one of the user before
+/// the one of the project.
+fn paths() -> Vec<PathBuf> {
+    let mut paths = vec![];
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".config")));
+    if let Some(dir) = config_home {
+        paths.push(dir.join("lev-diff").join("config.toml"));
+    }
+    if let Ok(dir) = env::current_dir() {
+        let project = dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file());
+        paths.extend(project);
+    }
+    paths
+}
+
+/// Turns the settings of the file at `path` into arguments for `command`,
+/// by the long name of their flag.
+fn file_args(
+    command: &Command,
+    path: &Path,
+    table: Table,
+) -> Result<BTreeMap<String, Vec<OsString>>, String> {
+    let mut args = BTreeMap::new();
+    for (key, value) in table {
+        if !SETTINGS.contains(&key.as_str()) {
+            return Err(format!("{}: `{key}` cannot be configured", path.display()));
+        }
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(&key))
+            .ok_or_else(|| format!("{}: unknown setting `{key}`", path.display()))?;
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        let mut key_args = vec![];
+        for value in values {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Integer(value) => value.to_string(),
+                Value::Float(value) => value.to_string(),
+                Value::Boolean(false) => continue,
+                Value::Boolean(true) if !arg.get_action().takes_values() => {
+                    key_args.push(format!("--{key}").into());
+                    continue;
+                }
+                Value::Boolean(true) => "true".to_string(),
+                _ => return Err(format!("{}: `{key}` must be a plain value", path.display())),
+            };
+            key_args.push(format!("--{key}={value}").into());
+        }
+        args.insert(key, key_args);
+    }
+    Ok(args)
+}
+
+/// Returns the arguments that the configuration files give `command`, for
+/// the flags that are not given on the command line parsed into `matches`.
+pub fn args(command: &Command, matches: &ArgMatches) -> Result<Vec<OsString>, Error> {
+    let mut settings = BTreeMap::new();
+    for path in paths() {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(format!("{}: {err}", path.display()).into()),
+        };
+        let table = text
+            .parse::<Table>()
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        settings.extend(file_args(command, &path, table)?);
+    }
+    let given = |key: &str| {
+        command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key))
+            .is_some_and(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+    };
+    Ok(settings
+        .into_iter()
+        .filter(|(key, _)| !given(key))
+        .flat_map(|(_, args)| args)
+        .collect())
+}
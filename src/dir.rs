@@ -0,0 +1,330 @@
+//! Pairing up the files of two directory trees.
+
+use std::{
+    collections::BTreeSet,
+    ffi::{OsStr, OsString},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobMatcher};
+
+/// A path found while walking two directories, relative to their roots.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// A regular file present on both sides.
+    Both(PathBuf),
+    /// A file or directory only present in the old tree.
+    OnlyOld(PathBuf),
+    /// A file or directory only present in the new tree.
+    OnlyNew(PathBuf),
+    /// A path that is a directory on one side and a file on the other, or
+    /// a symbolic link on one side only when links are not followed.
+    Mismatch(PathBuf),
+    /// A directory that is one of the directories containing it, as a
+    /// symbolic link to one of them is, and so is not descended into.
+    Loop(PathBuf),
+    /// A file only present in the old tree, paired by [`find_renames`] with
+    /// a similar file only present in the new tree, and how similar they
+    /// are.
+    Renamed(PathBuf, PathBuf, f64),
+}
+
+/// Names of files and directories to leave out of a comparison.
+#[derive(Clone, Debug, Default)]
+pub struct Exclude {
+    /// Every pattern, and whether it only matches directories.
+    patterns: Vec<(GlobMatcher, bool)>,
+}
+
+impl Exclude {
+    /// Excludes the files and directories whose name matches one of the
+    /// shell patterns `patterns`, such as `*.lock`. A pattern that ends
+    /// with `/`, such as `target/`, only matches directories.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self, globset::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let pattern = pattern.as_ref();
+                let dirs_only = pattern.len() > 1 && pattern.ends_with('/');
+                let glob = Glob::new(
+                    pattern
+                        .strip_suffix('/')
+                        .filter(|_| dirs_only)
+                        .unwrap_or(pattern),
+                )?;
+                Ok((glob.compile_matcher(), dirs_only))
+            })
+            .collect::<Result<_, globset::Error>>()?;
+        Ok(Exclude { patterns })
+    }
+
+    /// Returns whether the file or directory `name` is excluded.
+    pub fn excludes(&self, name: &OsStr, is_dir: bool) -> bool {
+        self.patterns
+            .iter()
+            .any(|(glob, dirs_only)| (is_dir || !dirs_only) && glob.is_match(name))
+    }
+}
+
+/// How two directory trees are walked.
+#[derive(Clone, Debug, Default)]
+pub struct Walk {
+    /// The files and directories to leave out.
+    pub exclude: Exclude,
+    /// Whether symbolic links are taken as files of their own instead of
+    /// being followed to what they point to.
+    pub no_dereference: bool,
+}
+
+/// What a path is, as far as pairing it up goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A regular file, or a path that doesn't exist.
+    File,
+    /// A directory.
+    Dir,
+    /// A symbolic link that is not followed.
+    Link,
+}
+
+/// A tree of files that can be compared with another, such as a directory
+/// or an archive. Paths are relative to its root.
+pub trait Tree {
+    /// Returns the names in the directory `rel`.
+    fn names(&self, rel: &Path) -> io::Result<BTreeSet<OsString>>;
+
+    /// Returns what `rel` is, following symbolic links unless
+    /// `no_dereference` is set.
+    fn kind(&self, rel: &Path, no_dereference: bool) -> Kind;
+
+    /// Returns the same path for the directory `rel` whichever path it is
+    /// reached by, so that loops can be found.
+    fn canonical(&self, rel: &Path) -> PathBuf;
+}
+
+/// A directory is the tree of the files inside it.
+impl Tree for Path {
+    fn names(&self, rel: &Path) -> io::Result<BTreeSet<OsString>> {
+        fs::read_dir(self.join(rel))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect()
+    }
+
+    fn kind(&self, rel: &Path, no_dereference: bool) -> Kind {
+        let path = self.join(rel);
+        if no_dereference && path.is_symlink() {
+            Kind::Link
+        } else if path.is_dir() {
+            Kind::Dir
+        } else {
+            Kind::File
+        }
+    }
+
+    /// Resolves every symbolic link, or returns the path as is if it can't.
+    fn canonical(&self, rel: &Path) -> PathBuf {
+        let dir = self.join(rel);
+        fs::canonicalize(&dir).unwrap_or(dir)
+    }
+}
+
+impl Walk {
+    fn kind<T: Tree + ?Sized>(&self, tree: &T, rel: &Path) -> Kind {
+        tree.kind(rel, self.no_dereference)
+    }
+
+    /// Walks the directory `rel` of both trees, where `ancestors` are the
+    /// canonical paths of the directories that contain it on either side.
+    fn walk<T1: Tree + ?Sized, T2: Tree + ?Sized>(
+        &self,
+        (old, new): (&T1, &T2),
+        rel: &Path,
+        ancestors: &mut Vec<(PathBuf, PathBuf)>,
+        result: &mut Vec<Entry>,
+    ) -> io::Result<()> {
+        let old_names = old.names(rel)?;
+        let new_names = new.names(rel)?;
+        for name in old_names.union(&new_names) {
+            let path = rel.join(name);
+            let kinds = (self.kind(old, &path), self.kind(new, &path));
+            let is_dir = kinds.0 == Kind::Dir || kinds.1 == Kind::Dir;
+            if self.exclude.excludes(name, is_dir) {
+                continue;
+            }
+            if !new_names.contains(name) {
+                result.push(Entry::OnlyOld(path));
+                continue;
+            }
+            if !old_names.contains(name) {
+                result.push(Entry::OnlyNew(path));
+                continue;
+            }
+            match kinds {
+                (Kind::Dir, Kind::Dir) => {
+                    let dirs = (old.canonical(&path), new.canonical(&path));
+                    if ancestors
+                        .iter()
+                        .any(|ancestor| ancestor.0 == dirs.0 || ancestor.1 == dirs.1)
+                    {
+                        result.push(Entry::Loop(path));
+                        continue;
+                    }
+                    ancestors.push(dirs);
+                    self.walk((old, new), &path, ancestors, result)?;
+                    ancestors.pop();
+                }
+                (kind1, kind2) if kind1 == kind2 => result.push(Entry::Both(path)),
+                _ => result.push(Entry::Mismatch(path)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks `old` and `new` recursively and returns every path found in
+    /// either of them that is not excluded, in sorted order.
+    ///
+    /// Directories present on both sides are descended into rather than
+    /// reported; a directory present on one side only is reported once.
+    pub fn compare<T1: Tree + ?Sized, T2: Tree + ?Sized>(
+        &self,
+        old: &T1,
+        new: &T2,
+    ) -> io::Result<Vec<Entry>> {
+        let mut result = vec![];
+        let root = Path::new("");
+        let mut ancestors = vec![(old.canonical(root), new.canonical(root))];
+        self.walk((old, new), root, &mut ancestors, &mut result)?;
+        Ok(result)
+    }
+
+    /// Adds an entry made by `only` for every file in the directory `rel`
+    /// of `tree` to `result`.
+    fn files<T: Tree + ?Sized>(
+        &self,
+        tree: &T,
+        rel: &Path,
+        only: fn(PathBuf) -> Entry,
+        ancestors: &mut Vec<PathBuf>,
+        result: &mut Vec<Entry>,
+    ) -> io::Result<()> {
+        for name in tree.names(rel)? {
+            let path = rel.join(&name);
+            let kind = self.kind(tree, &path);
+            if self.exclude.excludes(&name, kind == Kind::Dir) {
+                continue;
+            }
+            if kind != Kind::Dir {
+                result.push(only(path));
+                continue;
+            }
+            let dir = tree.canonical(&path);
+            if ancestors.contains(&dir) {
+                result.push(Entry::Loop(path));
+                continue;
+            }
+            ancestors.push(dir);
+            self.files(tree, &path, only, ancestors, result)?;
+            ancestors.pop();
+        }
+        Ok(())
+    }
+
+    /// Adds an entry made by `only` for `path` of `tree` to `result`, or
+    /// for every file inside it if it is a directory.
+    fn expand_only<T: Tree + ?Sized>(
+        &self,
+        tree: &T,
+        path: PathBuf,
+        only: fn(PathBuf) -> Entry,
+        result: &mut Vec<Entry>,
+    ) -> io::Result<()> {
+        if self.kind(tree, &path) != Kind::Dir {
+            result.push(only(path));
+            return Ok(());
+        }
+        let (root, dir) = (tree.canonical(Path::new("")), tree.canonical(&path));
+        if root == dir {
+            result.push(Entry::Loop(path));
+            return Ok(());
+        }
+        let mut ancestors = vec![root, dir];
+        self.files(tree, &path, only, &mut ancestors, result)
+    }
+
+    /// Replaces every directory of `entries`, as returned by
+    /// [`Walk::compare`], that is present on one side only with the files
+    /// inside it that are not excluded, so that each of them can be
+    /// compared to a missing file.
+    pub fn expand<T1: Tree + ?Sized, T2: Tree + ?Sized>(
+        &self,
+        old: &T1,
+        new: &T2,
+        entries: Vec<Entry>,
+    ) -> io::Result<Vec<Entry>> {
+        let mut result = vec![];
+        for entry in entries {
+            match entry {
+                Entry::OnlyOld(path) => self.expand_only(old, path, Entry::OnlyOld, &mut result)?,
+                Entry::OnlyNew(path) => self.expand_only(new, path, Entry::OnlyNew, &mut result)?,
+                entry => result.push(entry),
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Pairs the files of `entries` that are only present in the old tree with
+/// those only present in the new tree, replacing every pair whose
+/// `similarity` is at least `threshold` with an [`Entry::Renamed`] in the
+/// place of the old file.
+///
+/// The most similar candidates are paired first, so every file is paired
+/// with its best match among the files that are left. Directories are not
+/// paired; [`Walk::expand`] replaces them with their files.
+pub fn find_renames(
+    entries: Vec<Entry>,
+    threshold: f64,
+    similarity: impl Fn(&Path, &Path) -> f64,
+) -> Vec<Entry> {
+    let olds = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| match entry {
+            Entry::OnlyOld(path) => Some((i, path)),
+            _ => None,
+        });
+    let news = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| match entry {
+            Entry::OnlyNew(path) => Some((i, path)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut candidates = vec![];
+    for (i, old) in olds {
+        for &(j, new) in &news {
+            let score = similarity(old, new);
+            if score >= threshold {
+                candidates.push((score, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+    let mut entries = entries.into_iter().map(Some).collect::<Vec<_>>();
+    for (score, i, j) in candidates {
+        if !matches!(entries[i], Some(Entry::OnlyOld(_)))
+            || !matches!(entries[j], Some(Entry::OnlyNew(_)))
+        {
+            continue;
+        }
+        let (Some(Entry::OnlyOld(old)), Some(Entry::OnlyNew(new))) =
+            (entries[i].take(), entries[j].take())
+        else {
+            unreachable!("both files are unpaired");
+        };
+        entries[i] = Some(Entry::Renamed(old, new, score));
+    }
+    entries.into_iter().flatten().collect()
+}
@@ -0,0 +1,76 @@
+//! Index-based edit scripts shared by the diff backends.
+
+use crate::hook::DiffHook;
+
+/// A single step of an edit script, as 0-based indices into the old and new
+/// sequences.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+    Substitute(usize, usize),
+}
+
+impl Edit {
+    /// Moves the edit by `old` and `new` items, for edit scripts of a part
+    /// of the inputs.
+    pub(crate) fn shift(self, old: usize, new: usize) -> Self {
+        match self {
+            Edit::Equal(i, j) => Edit::Equal(old + i, new + j),
+            Edit::Delete(i) => Edit::Delete(old + i),
+            Edit::Insert(j) => Edit::Insert(new + j),
+            Edit::Substitute(i, j) => Edit::Substitute(old + i, new + j),
+        }
+    }
+}
+
+/// Walks an edit script, pairing the deletes and inserts of each run of
+/// changes into replacements if `pair` is set, and finishes `hook` at the
+/// end.
+pub(crate) fn replay<H: DiffHook + ?Sized>(edits: &[Edit], pair: bool, hook: &mut H) {
+    let mut deleted = vec![];
+    let mut inserted = vec![];
+    // The number of old and new items before the current run of changes.
+    let mut pos = (0, 0);
+    let flush = |hook: &mut H,
+                 deleted: &mut Vec<usize>,
+                 inserted: &mut Vec<usize>,
+                 (old, new): &mut (usize, usize)| {
+        let paired = match pair {
+            true => deleted.len().min(inserted.len()),
+            false => 0,
+        };
+        for (&i, &j) in deleted.iter().zip(inserted.iter()).take(paired) {
+            hook.on_replace(i, j);
+        }
+        for &i in &deleted[paired..] {
+            hook.on_delete(i, *new + paired);
+        }
+        for &j in &inserted[paired..] {
+            hook.on_insert(*old + deleted.len(), j);
+        }
+        *old += deleted.len();
+        *new += inserted.len();
+        deleted.clear();
+        inserted.clear();
+    };
+    for edit in edits {
+        match *edit {
+            Edit::Delete(i) => deleted.push(i),
+            Edit::Insert(j) => inserted.push(j),
+            Edit::Substitute(i, j) => {
+                flush(hook, &mut deleted, &mut inserted, &mut pos);
+                hook.on_replace(i, j);
+                pos = (i + 1, j + 1);
+            }
+            Edit::Equal(i, j) => {
+                flush(hook, &mut deleted, &mut inserted, &mut pos);
+                hook.on_equal(i, j);
+                pos = (i + 1, j + 1);
+            }
+        }
+    }
+    flush(hook, &mut deleted, &mut inserted, &mut pos);
+    hook.finish();
+}
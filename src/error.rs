@@ -0,0 +1,104 @@
+//! The errors that stop a run, all of which exit with status 2.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use lev_diff::{input::Encoding, patch, structure};
+
+/// Why a run failed.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An input doesn't exist.
+    #[error("{}: No such file or directory", .0.display())]
+    NotFound(PathBuf),
+    /// An input or the output may not be opened.
+    #[error("{}: Permission denied", .0.display())]
+    PermissionDenied(PathBuf),
+    /// An input exists but could not be read.
+    #[error("{}: cannot read: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    /// The output file could not be created.
+    #[error("{}: cannot create: {source}", path.display())]
+    Create { path: PathBuf, source: io::Error },
+    /// An input given as a URL could not be fetched.
+    #[error("{url}: cannot fetch: {reason}")]
+    Fetch { url: String, reason: String },
+    /// An input is not valid in the encoding given with `--encoding`.
+    #[error("{}: invalid {encoding} at byte {offset}", path.display())]
+    Encoding {
+        path: PathBuf,
+        encoding: Encoding,
+        offset: usize,
+    },
+    /// A structured document or a saved diff could not be parsed.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    #[error(transparent)]
+    Structure(#[from] structure::Error),
+    #[error(transparent)]
+    Patch(#[from] patch::Error),
+    /// A revision could not be read from the git repository.
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+    /// The inputs could not be watched for changes.
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
+    /// The threads for `--jobs` could not be started.
+    #[error(transparent)]
+    Jobs(#[from] rayon::ThreadPoolBuildError),
+    /// Reading from stdin or writing the output failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Anything else, described for the user.
+    #[error("{0}")]
+    Message(String),
+}
+
+impl Error {
+    /// Describes the failure `err` of opening or reading `path`.
+    pub fn read(path: &Path, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => Error::NotFound(path.to_path_buf()),
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied(path.to_path_buf()),
+            _ => Error::Read {
+                path: path.to_path_buf(),
+                source: err,
+            },
+        }
+    }
+
+    /// Describes the failure `err` of creating the output file `path`.
+    pub fn create(path: &Path, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => Error::PermissionDenied(path.to_path_buf()),
+            _ => Error::Create {
+                path: path.to_path_buf(),
+                source: err,
+            },
+        }
+    }
+
+    /// Returns whether the reader of the output went away, as a pager that
+    /// was quit or `head` does, which is not worth reporting.
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Error::Io(err) if err.kind() == io::ErrorKind::BrokenPipe)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Message(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Message(message.to_string())
+    }
+}
@@ -0,0 +1,60 @@
+//! Deciding which changes count as differences.
+
+use regex::Regex;
+
+use crate::{Action, Diff};
+
+/// Which kinds of changes to disregard.
+///
+/// A run of consecutive changes is only reported when at least one of its
+/// changes is not ignored, so ignored changes next to real ones are still
+/// shown, as with GNU diff.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Ignore changes that only add or remove blank lines.
+    pub blank_lines: bool,
+    /// Ignore changes whose lines all match one of these patterns.
+    pub matching: Vec<Regex>,
+}
+
+impl Filter {
+    fn ignores_line(&self, line: &str) -> bool {
+        (self.blank_lines && line.trim().is_empty())
+            || self.matching.iter().any(|regex| regex.is_match(line))
+    }
+
+    /// Returns `true` if `action` of `diff` is a change that does not count
+    /// on its own.
+    pub fn ignores(&self, diff: &Diff<&str>, action: &Action) -> bool {
+        let (old, new) = diff.items(action);
+        action.is_change()
+            && old
+                .into_iter()
+                .chain(new)
+                .all(|line| self.ignores_line(line))
+    }
+
+    /// Returns, for every action of `diff`, whether it is a change that
+    /// belongs to a run of changes that is not ignored entirely.
+    pub fn significant(&self, diff: &Diff<&str>) -> Vec<bool> {
+        let actions = diff.actions;
+        let mut result = vec![false; actions.len()];
+        let mut start = 0;
+        while start < actions.len() {
+            if !actions[start].is_change() {
+                start += 1;
+                continue;
+            }
+            let end = actions[start..]
+                .iter()
+                .position(|action| !action.is_change())
+                .map_or(actions.len(), |len| start + len);
+            let run = &actions[start..end];
+            if run.iter().any(|action| !self.ignores(diff, action)) {
+                result[start..end].fill(true);
+            }
+            start = end;
+        }
+        result
+    }
+}
@@ -0,0 +1,114 @@
+//! Context diff output, as produced by `diff -c`.
+
+use std::io::{self, Write};
+
+use crate::{filter::Filter, heading::Headings, hunk, Action, Diff};
+
+/// Formats the lines `start..start + count` as 1-based line numbers, or
+/// the line before them when there are none.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start}"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, start + count),
+    }
+}
+
+/// The old and the new lines of a hunk, each with its marker.
+#[derive(Default)]
+struct Sides<'a> {
+    old: Vec<(char, &'a str)>,
+    new: Vec<(char, &'a str)>,
+}
+
+impl<'a> Sides<'a> {
+    /// Adds a run of adjacent changes, marking the lines with `!` when the
+    /// run both removes and adds lines.
+    fn push_changes(&mut self, removed: &mut Vec<&'a str>, added: &mut Vec<&'a str>) {
+        let changed = !removed.is_empty() && !added.is_empty();
+        let (remove, add) = if changed { ('!', '!') } else { ('-', '+') };
+        self.old
+            .extend(removed.drain(..).map(|line| (remove, line)));
+        self.new.extend(added.drain(..).map(|line| (add, line)));
+    }
+}
+
+/// Writes the lines of one side of a hunk, marking the last one when
+/// `no_newline` tells that the file doesn't end with a newline after it.
+fn write_side<W: Write>(w: &mut W, lines: &[(char, &str)], no_newline: bool) -> io::Result<()> {
+    // A side without changes is left out, since its lines all show up on
+    // the other side.
+    if lines.iter().all(|&(marker, _)| marker == ' ') {
+        return Ok(());
+    }
+    for (marker, line) in lines {
+        writeln!(w, "{marker} {line}")?;
+    }
+    if no_newline {
+        writeln!(w, "\\ No newline at end of file")?;
+    }
+    Ok(())
+}
+
+/// Writes the edit script of `diff` as a context diff with `context` lines
+/// of context around each change. Hunks made up of changes ignored by
+/// `filter` are left out. With `headings`, the line that starts each hunk
+/// ends with the heading the hunk falls under.
+///
+/// `newlines` tells whether the old and the new file end with a newline;
+/// the last line of one that doesn't is marked like `diff -c` does.
+#[allow(clippy::too_many_arguments)]
+pub fn write<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    context: usize,
+    filter: &Filter,
+    newlines: (bool, bool),
+    headings: Option<&Headings>,
+) -> io::Result<()> {
+    let significant = filter.significant(diff);
+    for (n, group) in hunk::groups(&significant, context).into_iter().enumerate() {
+        if n == 0 {
+            writeln!(w, "*** {old_name}")?;
+            writeln!(w, "--- {new_name}")?;
+        }
+        let actions = &diff.actions[group];
+        let mut sides = Sides::default();
+        let (mut removed, mut added) = (vec![], vec![]);
+        for action in actions {
+            match *action {
+                Action::Add(_, j) => added.push(diff.new[j]),
+                Action::Remove(i, _) => removed.push(diff.old[i]),
+                Action::Substitute(i, j) => {
+                    removed.push(diff.old[i]);
+                    added.push(diff.new[j]);
+                }
+                Action::Ignore(i, j) => {
+                    sides.push_changes(&mut removed, &mut added);
+                    sides.old.push((' ', diff.old[i]));
+                    sides.new.push((' ', diff.new[j]));
+                }
+            }
+        }
+        sides.push_changes(&mut removed, &mut added);
+        let (old, new) = match actions[0] {
+            Action::Add(i, j)
+            | Action::Remove(i, j)
+            | Action::Substitute(i, j)
+            | Action::Ignore(i, j) => (i, j),
+        };
+        match headings.and_then(|headings| headings.last(&diff.old[..old])) {
+            Some(heading) => writeln!(w, "*************** {heading}")?,
+            None => writeln!(w, "***************")?,
+        }
+        let old_last = !newlines.0 && old + sides.old.len() == diff.old.len();
+        let new_last = !newlines.1 && new + sides.new.len() == diff.new.len();
+        writeln!(w, "*** {} ****", range(old, sides.old.len()))?;
+        write_side(w, &sides.old, old_last)?;
+        writeln!(w, "--- {} ----", range(new, sides.new.len()))?;
+        write_side(w, &sides.new, new_last)?;
+    }
+    Ok(())
+}
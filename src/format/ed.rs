@@ -0,0 +1,93 @@
+//! Edit scripts for the `ed` line editor, as produced by `diff -e`.
+
+use std::{
+    io::{self, Write},
+    ops::Range,
+};
+
+use crate::{filter::Filter, Action, Diff};
+
+/// A run of adjacent changes: the old lines it replaces and the new lines it
+/// replaces them with, either of which may be empty.
+pub(super) struct Block {
+    pub(super) old: Range<usize>,
+    pub(super) new: Range<usize>,
+}
+
+/// Groups the changes of `diff` that `filter` keeps into blocks, in order.
+pub(super) fn blocks(diff: &Diff<&str>, filter: &Filter) -> Vec<Block> {
+    let significant = filter.significant(diff);
+    let mut blocks: Vec<Block> = vec![];
+    let mut open = false;
+    for (action, significant) in diff.actions.iter().zip(significant) {
+        if !significant {
+            open = false;
+            continue;
+        }
+        let (i, j) = match *action {
+            Action::Add(i, j)
+            | Action::Remove(i, j)
+            | Action::Substitute(i, j)
+            | Action::Ignore(i, j) => (i, j),
+        };
+        if !open {
+            blocks.push(Block {
+                old: i..i,
+                new: j..j,
+            });
+            open = true;
+        }
+        let block = blocks.last_mut().unwrap();
+        if !matches!(action, Action::Add(..)) {
+            block.old.end = i + 1;
+        }
+        if !matches!(action, Action::Remove(..)) {
+            block.new.end = j + 1;
+        }
+    }
+    blocks
+}
+
+/// Formats a non-empty range of 0-based indices as 1-based line numbers.
+pub(super) fn lines(range: &Range<usize>) -> String {
+    match range.len() {
+        1 => format!("{}", range.start + 1),
+        _ => format!("{},{}", range.start + 1, range.end),
+    }
+}
+
+/// Writes the lines of `text` to be inserted, ending the insertion.
+fn write_text<W: Write>(w: &mut W, text: &[&str]) -> io::Result<()> {
+    for line in text {
+        if *line == "." {
+            // A lone dot would end the insertion, so insert two and remove
+            // one of them before going on.
+            writeln!(w, "..\n.\ns/.//\na")?;
+        } else {
+            writeln!(w, "{line}")?;
+        }
+    }
+    writeln!(w, ".")
+}
+
+/// Writes the changes of `diff` as an `ed` script that turns the old file
+/// into the new one.
+///
+/// The changes are written from the end of the file to its start, so that
+/// the line numbers of each command are still those of the old file when it
+/// runs. Changes ignored by `filter` are left out.
+pub fn write<W: Write>(w: &mut W, diff: &Diff<&str>, filter: &Filter) -> io::Result<()> {
+    for Block { old, new } in blocks(diff, filter).iter().rev() {
+        let text = &diff.new[new.clone()];
+        if old.is_empty() {
+            writeln!(w, "{}a", old.start)?;
+            write_text(w, text)?;
+        } else if new.is_empty() {
+            writeln!(w, "{}d", lines(old))?;
+        } else {
+            writeln!(w, "{}c", lines(old))?;
+            write_text(w, text)?;
+        }
+    }
+    Ok(())
+}
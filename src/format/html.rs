@@ -0,0 +1,175 @@
+//! Standalone HTML page with the two files side by side.
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use super::pretty::{spans, visible};
+use crate::{filter::Filter, Action, Diff};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 1em; }
+h1 { font-size: 1.2em; font-weight: normal; }
+.diff { font-family: monospace; font-size: 13px; border: 1px solid #d0d7de; }
+.row { display: grid; grid-template-columns: min-content 1fr min-content 1fr; }
+.num { min-width: 4ch; padding: 0 .5em; color: #6e7781; text-align: right; user-select: none; }
+.line { padding: 0 .5em; white-space: pre-wrap; overflow-wrap: anywhere; }
+.empty { background: #f6f8fa; }
+.remove { background: #ffebe9; }
+.add { background: #e6ffec; }
+.remove mark { background: #ffc1c0; }
+.add mark { background: #abf2bc; }
+.note { color: #57606a; }
+summary { padding: 0 .5em; background: #ddf4ff; color: #57606a; cursor: pointer; }
+";
+
+/// Escapes the characters of `text` that are special in HTML.
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Escapes `spans` and marks the changed ones.
+fn mark(spans: &[(bool, &str)]) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    while i < spans.len() {
+        let flag = spans[i].0;
+        let mut text = String::new();
+        while i < spans.len() && spans[i].0 == flag {
+            text.push_str(spans[i].1);
+            i += 1;
+        }
+        match flag {
+            true => result.push_str(&format!("<mark>{}</mark>", escape(&text))),
+            false => result.push_str(&escape(&text)),
+        }
+    }
+    result
+}
+
+/// One half of a row: the line number, the class of the line and its
+/// already escaped content, or nothing on the side that lacks the line.
+type Side = Option<(usize, &'static str, String)>;
+
+fn write_side<W: Write>(w: &mut W, side: Side) -> io::Result<()> {
+    match side {
+        Some((index, class, html)) => write!(
+            w,
+            r#"<span class="num">{}</span><span class="line {class}">{html}</span>"#,
+            index + 1
+        ),
+        None => write!(
+            w,
+            r#"<span class="num empty"></span><span class="line empty"></span>"#
+        ),
+    }
+}
+
+fn write_row<W: Write>(
+    w: &mut W,
+    diff: &Diff<&str>,
+    action: &Action,
+    significant: bool,
+) -> io::Result<()> {
+    let context = |index: usize, line: &str| Some((index, "context", escape(line).into_owned()));
+    let (old, new) = match *action {
+        Action::Add(_, j) if significant => {
+            (None, Some((j, "add", escape(diff.new[j]).into_owned())))
+        }
+        Action::Remove(i, _) if significant => {
+            (Some((i, "remove", escape(diff.old[i]).into_owned())), None)
+        }
+        Action::Substitute(i, j) if significant => {
+            let (old, new) = spans(diff.old[i], diff.new[j]);
+            (
+                Some((i, "remove", mark(&old))),
+                Some((j, "add", mark(&new))),
+            )
+        }
+        Action::Add(_, j) => (None, context(j, diff.new[j])),
+        Action::Remove(i, _) => (context(i, diff.old[i]), None),
+        Action::Substitute(i, j) | Action::Ignore(i, j) => {
+            (context(i, diff.old[i]), context(j, diff.new[j]))
+        }
+    };
+    write!(w, r#"<div class="row">"#)?;
+    write_side(w, old)?;
+    write_side(w, new)?;
+    writeln!(w, "</div>")
+}
+
+/// Writes `diff` as a standalone HTML page titled with `old_name` and
+/// `new_name`, with the old lines in the left pane and the new lines in the
+/// right one.
+///
+/// Removed and added lines are colored and the changed words of substituted
+/// lines are marked. Unchanged lines further than `context` lines from a
+/// change are folded into sections that expand when clicked. Changes ignored
+/// by `filter` are shown like unchanged lines. A `note`, such as that the
+/// line endings differ, is shown under the title.
+pub fn write<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    context: usize,
+    filter: &Filter,
+    note: Option<&str>,
+) -> io::Result<()> {
+    let (old_name, new_name) = (escape(old_name), escape(new_name));
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, r#"<html lang="en">"#)?;
+    writeln!(w, "<head>")?;
+    writeln!(w, r#"<meta charset="utf-8">"#)?;
+    writeln!(w, "<title>{old_name} → {new_name}</title>")?;
+    write!(w, "<style>\n{STYLE}</style>\n")?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(
+        w,
+        "<h1><code>{old_name}</code> → <code>{new_name}</code></h1>"
+    )?;
+    if let Some(note) = note {
+        writeln!(w, r#"<p class="note">{}</p>"#, escape(note))?;
+    }
+    writeln!(w, r#"<div class="diff">"#)?;
+    let actions = diff.actions;
+    let significant = filter.significant(diff);
+    let visible = visible(&significant, context);
+    let mut i = 0;
+    while i < actions.len() {
+        if visible[i] {
+            write_row(w, diff, &actions[i], significant[i])?;
+            i += 1;
+            continue;
+        }
+        let end = (i..actions.len())
+            .find(|&k| visible[k])
+            .unwrap_or(actions.len());
+        let hidden = end - i;
+        let noun = if hidden == 1 { "line" } else { "lines" };
+        writeln!(w, "<details><summary>{hidden} unchanged {noun}</summary>")?;
+        for k in i..end {
+            write_row(w, diff, &actions[k], significant[k])?;
+        }
+        writeln!(w, "</details>")?;
+        i = end;
+    }
+    writeln!(w, "</div>")?;
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")
+}
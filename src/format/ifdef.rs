@@ -0,0 +1,44 @@
+//! Merged output with preprocessor conditionals, as produced by `diff -D`.
+
+use std::io::{self, Write};
+
+use super::ed::{blocks, Block};
+use crate::{filter::Filter, Diff};
+
+/// Writes a merged file of both sides of `diff` in which the old lines of
+/// every change are only kept when `name` is undefined and the new lines
+/// only when it is defined.
+///
+/// Every change is wrapped, even those a filter would ignore, so that
+/// either file can be recovered by running the output through the
+/// preprocessor.
+pub fn write<W: Write>(w: &mut W, diff: &Diff<&str>, name: &str) -> io::Result<()> {
+    let mut next = 0;
+    for Block { old, new } in blocks(diff, &Filter::default()) {
+        for line in &diff.old[next..old.start] {
+            writeln!(w, "{line}")?;
+        }
+        match old.is_empty() {
+            true => writeln!(w, "#ifdef {name}")?,
+            false => writeln!(w, "#ifndef {name}")?,
+        }
+        for line in &diff.old[old.clone()] {
+            writeln!(w, "{line}")?;
+        }
+        if !old.is_empty() && !new.is_empty() {
+            writeln!(w, "#else /* {name} */")?;
+        }
+        for line in &diff.new[new.clone()] {
+            writeln!(w, "{line}")?;
+        }
+        match new.is_empty() {
+            true => writeln!(w, "#endif /* ! {name} */")?,
+            false => writeln!(w, "#endif /* {name} */")?,
+        }
+        next = old.end;
+    }
+    for line in &diff.old[next..] {
+        writeln!(w, "{line}")?;
+    }
+    Ok(())
+}
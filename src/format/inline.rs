@@ -0,0 +1,50 @@
+//! Inline rendering of token diffs, similar to `git diff --word-diff`.
+
+use std::io::{self, Write};
+
+use super::Theme;
+use crate::{Action, Diff};
+
+/// Writes the tokens of the edit script of `diff` in order, painting removed and added
+/// tokens with `theme`.
+///
+/// With a plain theme, removed tokens are wrapped in `[-…-]` and added ones
+/// in `{+…+}` instead. The tokens are expected to reproduce the original
+/// text when concatenated, as those returned by
+/// [`words`](crate::tokenize::words).
+pub fn write<W: Write>(w: &mut W, diff: &Diff<&str>, theme: &Theme) -> io::Result<()> {
+    let plain = theme.is_plain();
+    let add = |token: &str| match plain {
+        true => format!("{{+{token}+}}"),
+        false => theme.add.paint(token).to_string(),
+    };
+    let remove = |token: &str| match plain {
+        true => format!("[-{token}-]"),
+        false => theme.remove.paint(token).to_string(),
+    };
+    let mut last = "";
+    for action in diff.actions {
+        last = match *action {
+            Action::Add(_, j) => {
+                write!(w, "{}", add(diff.new[j]))?;
+                diff.new[j]
+            }
+            Action::Remove(i, _) => {
+                write!(w, "{}", remove(diff.old[i]))?;
+                diff.old[i]
+            }
+            Action::Substitute(i, j) => {
+                write!(w, "{}{}", remove(diff.old[i]), add(diff.new[j]))?;
+                diff.new[j]
+            }
+            Action::Ignore(i, _) => {
+                write!(w, "{}", theme.context.paint(diff.old[i]))?;
+                diff.old[i]
+            }
+        };
+    }
+    if !last.is_empty() && !last.ends_with('\n') {
+        writeln!(w)?;
+    }
+    Ok(())
+}
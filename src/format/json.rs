@@ -0,0 +1,198 @@
+//! JSON output for machine consumption.
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use serde::{de::Error as _, Deserialize, Serialize};
+
+use crate::{input::LineEnding, patch::Patch, Action, Diff};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Add,
+    Remove,
+    Substitute,
+    Ignore,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<'a> {
+    kind: Kind,
+    old_line: usize,
+    new_line: usize,
+    #[serde(borrow)]
+    content: Cow<'a, str>,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    old_content: Option<Cow<'a, str>>,
+}
+
+/// How the lines of each file end, when that differs between them.
+#[derive(Serialize, Deserialize)]
+struct LineEndings {
+    old: String,
+    new: String,
+}
+
+impl LineEndings {
+    fn new(endings: Option<(LineEnding, LineEnding)>) -> Option<Self> {
+        endings.map(|(old, new)| LineEndings {
+            old: old.to_string(),
+            new: new.to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Document<'a> {
+    #[serde(borrow)]
+    old_file: Cow<'a, str>,
+    #[serde(borrow)]
+    new_file: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line_endings: Option<LineEndings>,
+    #[serde(borrow)]
+    actions: Vec<Entry<'a>>,
+}
+
+/// An action of an NDJSON stream, which names the files so that every line
+/// stands on its own.
+#[derive(Serialize)]
+struct Record<'a> {
+    old_file: &'a str,
+    new_file: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_endings: Option<LineEndings>,
+    #[serde(flatten)]
+    entry: Entry<'a>,
+}
+
+fn entries<'a>(diff: &Diff<&'a str>) -> Vec<Entry<'a>> {
+    diff.actions
+        .iter()
+        .map(|action| match *action {
+            Action::Add(i, j) => Entry {
+                kind: Kind::Add,
+                old_line: i + 1,
+                new_line: j + 1,
+                content: Cow::Borrowed(diff.new[j]),
+                old_content: None,
+            },
+            Action::Remove(i, j) => Entry {
+                kind: Kind::Remove,
+                old_line: i + 1,
+                new_line: j + 1,
+                content: Cow::Borrowed(diff.old[i]),
+                old_content: None,
+            },
+            Action::Substitute(i, j) => Entry {
+                kind: Kind::Substitute,
+                old_line: i + 1,
+                new_line: j + 1,
+                content: Cow::Borrowed(diff.new[j]),
+                old_content: Some(Cow::Borrowed(diff.old[i])),
+            },
+            Action::Ignore(i, j) => Entry {
+                kind: Kind::Ignore,
+                old_line: i + 1,
+                new_line: j + 1,
+                content: Cow::Borrowed(diff.old[i]),
+                old_content: None,
+            },
+        })
+        .collect()
+}
+
+/// Writes the edit script of `diff` as a single JSON document.
+///
+/// Every action records its kind, its 1-based line number in each file and
+/// its content. A line that only exists on one side is numbered in the other
+/// file as the line it would come before. For substitutions `content` is the
+/// new line and `old_content` the old one. With `line_endings`, the line
+/// endings of the old and the new file are given as well.
+pub fn write<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    line_endings: Option<(LineEnding, LineEnding)>,
+) -> io::Result<()> {
+    let document = Document {
+        old_file: Cow::Borrowed(old_name),
+        new_file: Cow::Borrowed(new_name),
+        line_endings: LineEndings::new(line_endings),
+        actions: entries(diff),
+    };
+    serde_json::to_writer(&mut *w, &document)?;
+    writeln!(w)
+}
+
+/// Writes the edit script of `diff` as newline-delimited JSON, one object
+/// per action, so that it can be consumed while it is being written.
+///
+/// The objects are those of [`write`] with the names of both files and the
+/// `line_endings` added, and their line numbers are shifted by `offset` old
+/// and new lines for diffs of a part of the files.
+pub fn write_ndjson<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    line_endings: Option<(LineEnding, LineEnding)>,
+    offset: (usize, usize),
+) -> io::Result<()> {
+    for mut entry in entries(diff) {
+        entry.old_line += offset.0;
+        entry.new_line += offset.1;
+        let record = Record {
+            old_file: old_name,
+            new_file: new_name,
+            line_endings: LineEndings::new(line_endings),
+            entry,
+        };
+        serde_json::to_writer(&mut *w, &record)?;
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Reads back an edit script written by [`write`], as a patch that turns the
+/// old file into the new one.
+pub fn read(text: &str) -> serde_json::Result<Patch> {
+    let document: Document = serde_json::from_str(text)?;
+    let (mut old, mut new, mut actions) = (vec![], vec![], vec![]);
+    for entry in document.actions {
+        match entry.kind {
+            Kind::Add => {
+                actions.push(Action::Add(old.len(), new.len()));
+                new.push(entry.content);
+            }
+            Kind::Remove => {
+                actions.push(Action::Remove(old.len(), new.len()));
+                old.push(entry.content);
+            }
+            Kind::Substitute => {
+                let old_content = entry
+                    .old_content
+                    .ok_or_else(|| serde_json::Error::custom("missing `old_content`"))?;
+                actions.push(Action::Substitute(old.len(), new.len()));
+                old.push(old_content);
+                new.push(entry.content);
+            }
+            Kind::Ignore => {
+                actions.push(Action::Ignore(old.len(), new.len()));
+                old.push(entry.content.clone());
+                new.push(entry.content);
+            }
+        }
+    }
+    let old = old.iter().map(|line| line.as_ref()).collect::<Vec<_>>();
+    let new = new.iter().map(|line| line.as_ref()).collect::<Vec<_>>();
+    Ok(Patch::from_actions(&Diff {
+        old: &old,
+        new: &new,
+        actions: &actions,
+    }))
+}
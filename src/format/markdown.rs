@@ -0,0 +1,92 @@
+//! Markdown output, for pasting into issue comments and chat tools.
+
+use std::io::{self, Write};
+
+use super::unified;
+use crate::{filter::Filter, Action, Diff};
+
+/// Returns the length of the longest run of backticks in `text`.
+fn longest_backticks(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Formats `text` as an inline code span that is safe to put in a table
+/// cell.
+fn code(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    let fence = "`".repeat(longest_backticks(text) + 1);
+    // Code spans lose one space on each side, which keeps a backtick at
+    // either end from joining the fence.
+    let pad = match text.starts_with('`') || text.ends_with('`') {
+        true => " ",
+        false => "",
+    };
+    let text = text.replace('|', "\\|");
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+/// Writes `diff` as a unified diff with `context` lines of context inside
+/// a fenced code block tagged `diff`, so that it is highlighted where it is
+/// rendered.
+///
+/// Nothing is written when `diff` has no changes that `filter` keeps, and
+/// `newlines` tells whether the files end with a newline, as for
+/// [`unified::write`].
+pub fn write<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    context: usize,
+    filter: &Filter,
+    newlines: (bool, bool),
+) -> io::Result<()> {
+    let mut body = vec![];
+    unified::write(
+        &mut body, old_name, new_name, diff, context, filter, newlines, None,
+    )?;
+    if body.is_empty() {
+        return Ok(());
+    }
+    let body = String::from_utf8_lossy(&body);
+    let fence = "`".repeat(longest_backticks(&body).max(2) + 1);
+    writeln!(w, "{fence}diff")?;
+    write!(w, "{body}")?;
+    writeln!(w, "{fence}")
+}
+
+/// Writes `diff` as a table with the lines of `old_name` on the left and
+/// those of `new_name` on the right, each with its line number.
+///
+/// The middle column marks removed lines with `-`, added lines with `+` and
+/// substituted lines with `~`.
+pub fn write_table<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+) -> io::Result<()> {
+    writeln!(w, "| | {} | | | {} |", code(old_name), code(new_name))?;
+    writeln!(w, "|--:|---|:-:|--:|---|")?;
+    for action in diff.actions {
+        let (old, marker, new) = match *action {
+            Action::Add(_, j) => (None, "+", Some(j)),
+            Action::Remove(i, _) => (Some(i), "-", None),
+            Action::Substitute(i, j) => (Some(i), "~", Some(j)),
+            Action::Ignore(i, j) => (Some(i), "", Some(j)),
+        };
+        let cells = |index: Option<usize>, lines: &[&str]| match index {
+            Some(index) => (format!("{}", index + 1), code(lines[index])),
+            None => (String::new(), String::new()),
+        };
+        let (old_number, old_line) = cells(old, diff.old);
+        let (new_number, new_line) = cells(new, diff.new);
+        writeln!(
+            w,
+            "| {old_number} | {old_line} | {marker} | {new_number} | {new_line} |"
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Merged output with conflict markers, as produced by `diff3 -m`.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::merge::Region;
+
+/// How conflicts are presented.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictStyle {
+    /// Only our and their lines.
+    #[default]
+    Merge,
+    /// Our, the base and their lines.
+    Diff3,
+    /// Like [`ConflictStyle::Diff3`], with lines that both sides agree on at
+    /// the start and end of a conflict moved out of it.
+    Zdiff3,
+}
+
+/// Names shown next to the conflict markers.
+#[derive(Clone, Copy, Debug)]
+pub struct Labels<'a> {
+    /// The common ancestor.
+    pub base: &'a str,
+    /// Our side.
+    pub ours: &'a str,
+    /// Their side.
+    pub theirs: &'a str,
+}
+
+fn write_lines<W: Write, T: Display>(w: &mut W, lines: &[T]) -> io::Result<()> {
+    for line in lines {
+        writeln!(w, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Returns the lengths of the longest common prefix and suffix of `a` and
+/// `b` that do not overlap.
+fn common_ends<T: PartialEq>(a: &[T], b: &[T]) -> (usize, usize) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (prefix, suffix)
+}
+
+/// Writes the merged lines, wrapping every conflict in markers labelled
+/// with `labels`.
+///
+/// The `<<<<<<<` and `>>>>>>>` markers surround our and their lines, which
+/// are separated by `=======`. The diff3 styles additionally show the base
+/// lines after a `|||||||` marker.
+pub fn write<W: Write, T: Display + PartialEq>(
+    w: &mut W,
+    regions: &[Region<T>],
+    style: ConflictStyle,
+    labels: Labels,
+) -> io::Result<()> {
+    for region in regions {
+        let (base, mut ours, mut theirs) = match region {
+            Region::Resolved(lines) => {
+                write_lines(w, lines)?;
+                continue;
+            }
+            Region::Conflict { base, ours, theirs } => (&base[..], &ours[..], &theirs[..]),
+        };
+        let mut suffix: &[T] = &[];
+        if style == ConflictStyle::Zdiff3 {
+            let (start, end) = common_ends(ours, theirs);
+            write_lines(w, &ours[..start])?;
+            suffix = &ours[ours.len() - end..];
+            ours = &ours[start..ours.len() - end];
+            theirs = &theirs[start..theirs.len() - end];
+        }
+        writeln!(w, "<<<<<<< {}", labels.ours)?;
+        write_lines(w, ours)?;
+        if style != ConflictStyle::Merge {
+            writeln!(w, "||||||| {}", labels.base)?;
+            write_lines(w, base)?;
+        }
+        writeln!(w, "=======")?;
+        write_lines(w, theirs)?;
+        writeln!(w, ">>>>>>> {}", labels.theirs)?;
+        write_lines(w, suffix)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! Renderers that turn an edit script into text.
+
+use std::str::FromStr;
+
+use ansi_term::{Color, Style};
+
+pub mod context;
+pub mod ed;
+pub mod html;
+pub mod ifdef;
+pub mod inline;
+pub mod json;
+pub mod markdown;
+pub mod merge;
+pub mod normal;
+pub mod notebook;
+pub mod pretty;
+pub mod side_by_side;
+pub mod stat;
+pub mod structure;
+pub mod unified;
+
+/// A built-in set of styles for the colored renderers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// Green, red and yellow, for dark terminal backgrounds.
+    #[default]
+    Default,
+    /// Blue and orange, which stay apart with red-green color blindness.
+    Deuteranopia,
+    /// Dark colors without yellow, for light terminal backgrounds.
+    Light,
+    /// No colors, only bold, dim and italic text.
+    Mono,
+}
+
+/// The symbols that mark the kind of each change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Markers {
+    /// Added lines.
+    pub add: String,
+    /// Removed lines.
+    pub remove: String,
+    /// Substituted lines.
+    pub substitute: String,
+    /// What separates the old and the new side of a substituted line.
+    pub arrow: String,
+    /// What separates the old and the new value of a changed key of a
+    /// document.
+    pub to: String,
+}
+
+impl Markers {
+    /// Markers made of ASCII characters only, for terminals without Unicode.
+    pub fn ascii() -> Self {
+        Markers {
+            arrow: "->".to_string(),
+            to: "->".to_string(),
+            ..Markers::default()
+        }
+    }
+
+    /// Markers that look like those of `diff`.
+    pub fn diff() -> Self {
+        Markers {
+            add: ">".to_string(),
+            remove: "<".to_string(),
+            substitute: "|".to_string(),
+            arrow: "|".to_string(),
+            to: "->".to_string(),
+        }
+    }
+
+    /// Returns the width of the widest marker of a line, which the others
+    /// are padded to.
+    pub fn width(&self) -> usize {
+        [&self.add, &self.remove, &self.substitute]
+            .iter()
+            .map(|marker| marker.chars().count())
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Markers {
+            add: "+".to_string(),
+            remove: "-".to_string(),
+            substitute: "~".to_string(),
+            arrow: "⇆".to_string(),
+            to: "→".to_string(),
+        }
+    }
+}
+
+/// Parses markers such as `ascii`, `diff` or `add=>,remove=<`.
+///
+/// Every entry either names a set of markers, `default`, `ascii` or
+/// `diff`, or overrides one marker of those before it; the keys are `add`,
+/// `remove`, `substitute`, `arrow` and `to`.
+impl FromStr for Markers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut markers = Markers::default();
+        for entry in s.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let Some((key, value)) = entry.split_once('=') else {
+                markers = match entry.trim() {
+                    "default" => Markers::default(),
+                    "ascii" => Markers::ascii(),
+                    "diff" => Markers::diff(),
+                    name => return Err(format!("unknown markers `{name}`")),
+                };
+                continue;
+            };
+            let value = value.to_string();
+            match key.trim() {
+                "add" => markers.add = value,
+                "remove" => markers.remove = value,
+                "substitute" => markers.substitute = value,
+                "arrow" => markers.arrow = value,
+                "to" => markers.to = value,
+                key => return Err(format!("unknown marker key `{key}`")),
+            }
+        }
+        Ok(markers)
+    }
+}
+
+/// Styles and markers used by the colored renderers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Added lines.
+    pub add: Style,
+    /// Removed lines.
+    pub remove: Style,
+    /// The marker of substituted lines.
+    pub substitute: Style,
+    /// Removed and added lines that were moved elsewhere.
+    pub moved: Style,
+    /// Unchanged lines.
+    pub context: Style,
+    /// The symbols that mark changes.
+    pub markers: Markers,
+}
+
+impl Theme {
+    /// The theme of `palette`.
+    pub fn palette(palette: Palette) -> Self {
+        match palette {
+            Palette::Default => Theme {
+                add: Color::Green.normal(),
+                remove: Color::Red.normal(),
+                substitute: Color::Yellow.normal(),
+                moved: Color::Blue.normal(),
+                context: Style::new(),
+                markers: Markers::default(),
+            },
+            Palette::Deuteranopia => Theme {
+                add: Color::Fixed(33).normal(),
+                remove: Color::Fixed(208).normal(),
+                substitute: Color::Purple.normal(),
+                moved: Color::Cyan.normal(),
+                context: Style::new(),
+                markers: Markers::default(),
+            },
+            Palette::Light => Theme {
+                add: Color::Fixed(28).normal(),
+                remove: Color::Fixed(124).normal(),
+                substitute: Color::Fixed(130).normal(),
+                moved: Color::Fixed(25).normal(),
+                context: Style::new(),
+                markers: Markers::default(),
+            },
+            Palette::Mono => Theme {
+                add: Style::new().bold(),
+                remove: Style::new().dimmed(),
+                substitute: Style::new().bold(),
+                moved: Style::new().italic(),
+                context: Style::new(),
+                markers: Markers::default(),
+            },
+        }
+    }
+
+    /// Overrides styles of the theme with a spec such as
+    /// `add=blue,remove=#ff8800,context=dim`.
+    ///
+    /// The keys are `add`, `remove`, `substitute`, `moved` and `context`.
+    pub fn overridden(mut self, spec: &str) -> Result<Self, String> {
+        for entry in spec.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=style`, found `{entry}`"))?;
+            let style = parse_style(value)?;
+            match key.trim() {
+                "add" => self.add = style,
+                "remove" => self.remove = style,
+                "substitute" => self.substitute = style,
+                "moved" => self.moved = style,
+                "context" => self.context = style,
+                key => return Err(format!("unknown theme key `{key}`")),
+            }
+        }
+        Ok(self)
+    }
+
+    /// A theme without any colors, for output that is not a terminal.
+    pub fn plain() -> Self {
+        Theme {
+            add: Style::new(),
+            remove: Style::new(),
+            substitute: Style::new(),
+            moved: Style::new(),
+            context: Style::new(),
+            markers: Markers::default(),
+        }
+    }
+
+    /// Returns `true` if the theme emits no escape codes at all, whatever its
+    /// markers.
+    pub fn is_plain(&self) -> bool {
+        [
+            self.add,
+            self.remove,
+            self.substitute,
+            self.moved,
+            self.context,
+        ]
+        .iter()
+        .all(|style| style.is_plain())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::palette(Palette::Default)
+    }
+}
+
+fn parse_color(spec: &str) -> Option<Color> {
+    let color = match spec {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" | "magenta" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        _ => {
+            if let Some(hex) = spec.strip_prefix('#') {
+                let value = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 6)?;
+                let [_, r, g, b] = value.to_be_bytes();
+                Color::RGB(r, g, b)
+            } else {
+                Color::Fixed(spec.parse().ok()?)
+            }
+        }
+    };
+    Some(color)
+}
+
+/// Parses a style such as `bold+green`, `214` or `#ff8800`.
+///
+/// A style is a `+` separated list of modifiers (`bold`, `dim`, `italic`,
+/// `underline`) and at most one color: a name, a 256-color index, or a
+/// 24-bit `#rrggbb` value. `none` is the style without any attributes.
+fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::new();
+    for part in spec.split('+').map(str::trim) {
+        style = match part {
+            "none" => style,
+            "bold" => style.bold(),
+            "dim" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            _ => match parse_color(part) {
+                Some(color) => style.fg(color),
+                None => return Err(format!("invalid style `{part}`")),
+            },
+        };
+    }
+    Ok(style)
+}
+
+/// Parses a theme such as `add=blue,remove=#ff8800,context=dim`, which
+/// overrides styles of the default theme, see [`Theme::overridden`].
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::default().overridden(s)
+    }
+}
@@ -0,0 +1,49 @@
+//! The default output of POSIX `diff`, with `a`, `c` and `d` commands
+//! followed by the lines they affect.
+
+use std::io::{self, Write};
+
+use super::ed::{blocks, lines, Block};
+use crate::{filter::Filter, Diff};
+
+/// Writes the changes of `diff` in the normal format of `diff`, with removed
+/// lines prefixed by `<` and added lines by `>`.
+///
+/// Changes ignored by `filter` are left out. `newlines` tells whether the
+/// old and the new file end with a newline; the last line of one that
+/// doesn't is marked like `diff` does.
+pub fn write<W: Write>(
+    w: &mut W,
+    diff: &Diff<&str>,
+    filter: &Filter,
+    newlines: (bool, bool),
+) -> io::Result<()> {
+    let marker =
+        |w: &mut W, ends_with_newline: bool, index: usize, len: usize| match !ends_with_newline
+            && index + 1 == len
+        {
+            true => writeln!(w, "\\ No newline at end of file"),
+            false => Ok(()),
+        };
+    for Block { old, new } in blocks(diff, filter) {
+        if old.is_empty() {
+            writeln!(w, "{}a{}", old.start, lines(&new))?;
+        } else if new.is_empty() {
+            writeln!(w, "{}d{}", lines(&old), new.start)?;
+        } else {
+            writeln!(w, "{}c{}", lines(&old), lines(&new))?;
+        }
+        for i in old.clone() {
+            writeln!(w, "< {}", diff.old[i])?;
+            marker(w, newlines.0, i, diff.old.len())?;
+        }
+        if !old.is_empty() && !new.is_empty() {
+            writeln!(w, "---")?;
+        }
+        for j in new {
+            writeln!(w, "> {}", diff.new[j])?;
+            marker(w, newlines.1, j, diff.new.len())?;
+        }
+    }
+    Ok(())
+}
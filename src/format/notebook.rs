@@ -0,0 +1,88 @@
+//! Listing of the differences between two notebooks, cell by cell.
+
+use std::io::{self, Write};
+
+use super::{pretty, Theme};
+use crate::{filter::Filter, lev, notebook::Cell, options::DiffOptions, Action, Diff};
+
+fn lines(cell: Option<&Cell>) -> Vec<&str> {
+    match cell {
+        Some(cell) => cell.lines.iter().map(String::as_str).collect(),
+        None => vec![],
+    }
+}
+
+/// Returns what cells are aligned by.
+fn key(cell: &Cell) -> (&str, &[String]) {
+    (&cell.kind, &cell.source)
+}
+
+/// Writes the cells that were added, removed or changed from `old` to `new`,
+/// each under a line naming it, colored with `theme`.
+///
+/// The cells are aligned by their type and source with [`lev`], and the
+/// lines of every cell are diffed with `opts` and listed like the pretty
+/// format does, with `context` unchanged lines around each change. Returns
+/// whether any cell differs.
+pub fn write<W: Write>(
+    w: &mut W,
+    old: &[Cell],
+    new: &[Cell],
+    opts: &DiffOptions,
+    context: Option<usize>,
+    filter: &Filter,
+    theme: &Theme,
+) -> io::Result<bool> {
+    let mut differs = false;
+    let (keys1, keys2) = (
+        old.iter().map(key).collect::<Vec<_>>(),
+        new.iter().map(key).collect::<Vec<_>>(),
+    );
+    for action in lev(&keys1, &keys2) {
+        let (cell1, cell2, marker, number) = match action {
+            Action::Add(_, j) => (None, Some(&new[j]), theme.add.paint(&theme.markers.add), j),
+            Action::Remove(i, _) => (
+                Some(&old[i]),
+                None,
+                theme.remove.paint(&theme.markers.remove),
+                i,
+            ),
+            Action::Substitute(i, j) | Action::Ignore(i, j) => (
+                Some(&old[i]),
+                Some(&new[j]),
+                theme.substitute.paint(&theme.markers.substitute),
+                j,
+            ),
+        };
+        let (lines1, lines2) = (lines(cell1), lines(cell2));
+        let actions = opts.diff(&lines1, &lines2);
+        let diff = Diff {
+            old: &lines1,
+            new: &lines2,
+            actions: &actions,
+        };
+        // Cells with the same source may still differ in their outputs, and
+        // any cell only in ways that `filter` ignores.
+        let kind_changed = cell1
+            .zip(cell2)
+            .is_some_and(|(cell1, cell2)| cell1.kind != cell2.kind);
+        if cell1.is_some()
+            && cell2.is_some()
+            && !kind_changed
+            && !filter.significant(&diff).contains(&true)
+        {
+            continue;
+        }
+        differs = true;
+        let kind = match (cell1, cell2) {
+            (Some(cell1), Some(cell2)) if kind_changed => {
+                format!("{} → {}", cell1.kind, cell2.kind)
+            }
+            (Some(cell), _) | (_, Some(cell)) => cell.kind.clone(),
+            (None, None) => unreachable!(),
+        };
+        writeln!(w, "{marker} cell {} ({kind})", number + 1)?;
+        pretty::write(w, &diff, context, filter, None, None, None, theme)?;
+    }
+    Ok(differs)
+}
@@ -0,0 +1,385 @@
+//! Colored listing of the edit script, one action per line.
+
+use std::io::{self, Write};
+
+use ansi_term::{Color, Style};
+
+use super::{unified, Theme};
+use crate::{filter::Filter, heading::Headings, hunk, lev, moved::Moved, tokenize, Action, Diff};
+
+/// Returns which actions lie within `context` actions of a significant
+/// change.
+pub(super) fn visible(significant: &[bool], context: usize) -> Vec<bool> {
+    let mut result = vec![false; significant.len()];
+    for range in hunk::groups(significant, context) {
+        result[range].fill(true);
+    }
+    result
+}
+
+/// Paints `spans` with `base`, emphasizing the changed ones unless `base`
+/// is plain.
+fn paint(spans: &[(bool, &str)], base: Style) -> String {
+    let changed = match base.is_plain() {
+        true => base,
+        false => base.bold().underline(),
+    };
+    let mut result = String::new();
+    let mut i = 0;
+    while i < spans.len() {
+        let flag = spans[i].0;
+        let mut text = String::new();
+        while i < spans.len() && spans[i].0 == flag {
+            text.push_str(spans[i].1);
+            i += 1;
+        }
+        let style = if flag { changed } else { base };
+        result.push_str(&style.paint(text).to_string());
+    }
+    result
+}
+
+/// Spans of a line, flagged when they changed.
+pub(super) type Spans<'a> = Vec<(bool, &'a str)>;
+
+/// Diffs the words of a substituted line pair and returns the spans of each
+/// line, flagged when they changed.
+pub(super) fn spans<'a>(line1: &'a str, line2: &'a str) -> (Spans<'a>, Spans<'a>) {
+    let mut old = vec![];
+    let mut new = vec![];
+    let (tokens1, tokens2) = (tokenize::words(line1), tokenize::words(line2));
+    for action in lev(&tokens1, &tokens2) {
+        match action {
+            Action::Add(_, j) => new.push((true, tokens2[j])),
+            Action::Remove(i, _) => old.push((true, tokens1[i])),
+            Action::Substitute(i, j) => {
+                old.push((true, tokens1[i]));
+                new.push((true, tokens2[j]));
+            }
+            Action::Ignore(i, j) => {
+                old.push((false, tokens1[i]));
+                new.push((false, tokens2[j]));
+            }
+        }
+    }
+    (old, new)
+}
+
+/// Syntax-highlighted spans of the old and the new lines of a diff.
+pub struct Highlighted<'a> {
+    /// The spans of every old line.
+    pub old: Vec<Vec<(Style, &'a str)>>,
+    /// The spans of every new line.
+    pub new: Vec<Vec<(Style, &'a str)>>,
+}
+
+/// The backgrounds of highlighted removed lines and their changed words.
+const REMOVED: (Color, Color) = (Color::RGB(0x3f, 0x1f, 0x1f), Color::RGB(0x7f, 0x2f, 0x2f));
+/// The backgrounds of highlighted added lines and their changed words.
+const ADDED: (Color, Color) = (Color::RGB(0x1f, 0x3f, 0x1f), Color::RGB(0x2f, 0x6f, 0x2f));
+
+/// Paints the syntax spans of a line, over the first of `backgrounds` and
+/// the second where `changed` flags the text.
+fn paint_syntax(
+    syntax: &[(Style, &str)],
+    changed: &[(bool, &str)],
+    backgrounds: Option<(Color, Color)>,
+) -> String {
+    let flags: Vec<bool> = changed
+        .iter()
+        .flat_map(|&(flag, text)| std::iter::repeat_n(flag, text.len()))
+        .collect();
+    let mut result = String::new();
+    let mut offset = 0;
+    for &(style, text) in syntax {
+        let mut start = 0;
+        while start < text.len() {
+            let flag = flags.get(offset + start).copied().unwrap_or(false);
+            let end = (start + 1..=text.len())
+                .find(|&end| {
+                    end == text.len()
+                        || text.is_char_boundary(end)
+                            && flags.get(offset + end).copied().unwrap_or(false) != flag
+                })
+                .unwrap_or(text.len());
+            let style = match backgrounds {
+                Some((line, word)) => style.on(if flag { word } else { line }),
+                None => style,
+            };
+            result.push_str(&style.paint(&text[start..end]).to_string());
+            start = end;
+        }
+        offset += text.len();
+    }
+    result
+}
+
+/// Paints the lines of a diff: with the styles of a theme, or over their
+/// syntax highlighting when there is some.
+struct Painter<'a> {
+    theme: &'a Theme,
+    moved: Option<&'a Moved>,
+    highlighted: Option<&'a Highlighted<'a>>,
+}
+
+impl Painter<'_> {
+    fn is_moved_old(&self, i: usize) -> bool {
+        self.moved.is_some_and(|moved| moved.old[i])
+    }
+
+    fn is_moved_new(&self, j: usize) -> bool {
+        self.moved.is_some_and(|moved| moved.new[j])
+    }
+
+    fn remove_style(&self, i: usize) -> Style {
+        match self.is_moved_old(i) {
+            true => self.theme.moved,
+            false => self.theme.remove,
+        }
+    }
+
+    fn add_style(&self, j: usize) -> Style {
+        match self.is_moved_new(j) {
+            true => self.theme.moved,
+            false => self.theme.add,
+        }
+    }
+
+    /// Paints the old line `i` as unchanged.
+    fn unchanged_old(&self, i: usize, text: &str) -> String {
+        match self.highlighted {
+            Some(highlighted) => paint_syntax(&highlighted.old[i], &[], None),
+            None => self.theme.context.paint(text).to_string(),
+        }
+    }
+
+    /// Paints the new line `j` as unchanged.
+    fn unchanged_new(&self, j: usize, text: &str) -> String {
+        match self.highlighted {
+            Some(highlighted) => paint_syntax(&highlighted.new[j], &[], None),
+            None => self.theme.context.paint(text).to_string(),
+        }
+    }
+
+    /// Paints the old line `i` as removed, emphasizing the spans that
+    /// `changed` flags.
+    fn removed(&self, i: usize, text: &str, changed: &[(bool, &str)]) -> String {
+        match self.highlighted {
+            // Moved lines keep their own style, which tells them apart.
+            Some(highlighted) if !self.is_moved_old(i) => {
+                paint_syntax(&highlighted.old[i], changed, Some(REMOVED))
+            }
+            _ if changed.is_empty() => self.remove_style(i).paint(text).to_string(),
+            _ => paint(changed, self.remove_style(i)),
+        }
+    }
+
+    /// Paints the new line `j` as added, emphasizing the spans that
+    /// `changed` flags.
+    fn added(&self, j: usize, text: &str, changed: &[(bool, &str)]) -> String {
+        match self.highlighted {
+            Some(highlighted) if !self.is_moved_new(j) => {
+                paint_syntax(&highlighted.new[j], changed, Some(ADDED))
+            }
+            _ if changed.is_empty() => self.add_style(j).paint(text).to_string(),
+            _ => paint(changed, self.add_style(j)),
+        }
+    }
+}
+
+fn write_action<W: Write>(
+    w: &mut W,
+    diff: &Diff<&str>,
+    action: &Action,
+    width: usize,
+    significant: bool,
+    painter: &Painter,
+) -> io::Result<()> {
+    let number = |index: usize| format!("{:>width$}", index + 1);
+    let markers = &painter.theme.markers;
+    let pad = markers.width();
+    let (blank, arrow) = (" ".repeat(pad), &markers.arrow);
+    let (old, new) = match *action {
+        Action::Add(_, j) => (" ".repeat(width), number(j)),
+        Action::Remove(i, _) => (number(i), " ".repeat(width)),
+        Action::Substitute(i, j) | Action::Ignore(i, j) => (number(i), number(j)),
+    };
+    if action.is_change() && !significant {
+        return match *action {
+            Action::Substitute(i, j) => writeln!(
+                w,
+                "{old} {new} {blank}| {line1} {arrow}  {line2}",
+                line1 = painter.unchanged_old(i, diff.old[i]),
+                line2 = painter.unchanged_new(j, diff.new[j]),
+            ),
+            Action::Add(_, j) => writeln!(
+                w,
+                "{old} {new} {blank}| {}",
+                painter.unchanged_new(j, diff.new[j])
+            ),
+            Action::Remove(i, _) | Action::Ignore(i, _) => {
+                writeln!(
+                    w,
+                    "{old} {new} {blank}| {}",
+                    painter.unchanged_old(i, diff.old[i])
+                )
+            }
+        };
+    }
+    match *action {
+        Action::Add(_, j) => writeln!(
+            w,
+            "{old} {new} {action}| {line}",
+            action = painter.add_style(j).paint(format!("{:<pad$}", markers.add)),
+            line = painter.added(j, diff.new[j], &[]),
+        ),
+        Action::Remove(i, _) => writeln!(
+            w,
+            "{old} {new} {action}| {line}",
+            action = painter
+                .remove_style(i)
+                .paint(format!("{:<pad$}", markers.remove)),
+            line = painter.removed(i, diff.old[i], &[]),
+        ),
+        Action::Substitute(i, j) => {
+            // Only the changed words of the pair are emphasized.
+            let (spans1, spans2) = spans(diff.old[i], diff.new[j]);
+            writeln!(
+                w,
+                "{old} {new} {action}| {line1} {arrow}  {line2}",
+                action = painter
+                    .theme
+                    .substitute
+                    .paint(format!("{:<pad$}", markers.substitute)),
+                line1 = painter.removed(i, diff.old[i], &spans1),
+                line2 = painter.added(j, diff.new[j], &spans2),
+            )
+        }
+        Action::Ignore(i, _) => writeln!(
+            w,
+            "{old} {new} {blank}| {}",
+            painter.unchanged_old(i, diff.old[i])
+        ),
+    }
+}
+
+/// Writes the line that starts a group of shown actions, giving the old and
+/// the new lines they span like a unified diff hunk header, and the heading
+/// that `headings` finds before them.
+fn write_header<W: Write>(
+    w: &mut W,
+    diff: &Diff<&str>,
+    actions: &[Action],
+    headings: Option<&Headings>,
+    theme: &Theme,
+) -> io::Result<()> {
+    let (old, new) = match actions[0] {
+        Action::Add(i, j)
+        | Action::Remove(i, j)
+        | Action::Substitute(i, j)
+        | Action::Ignore(i, j) => (i, j),
+    };
+    let old_count = actions
+        .iter()
+        .filter(|action| !matches!(action, Action::Add(..)));
+    let new_count = actions
+        .iter()
+        .filter(|action| !matches!(action, Action::Remove(..)));
+    let mut header = format!(
+        "@@ -{} +{} @@",
+        unified::range(old, old_count.count()),
+        unified::range(new, new_count.count()),
+    );
+    if let Some(heading) = headings.and_then(|headings| headings.last(&diff.old[..old])) {
+        header.push(' ');
+        header.push_str(heading);
+    }
+    writeln!(w, "{}", theme.context.paint(header))
+}
+
+fn write_collapsed<W: Write>(w: &mut W, hidden: usize) -> io::Result<()> {
+    let noun = if hidden == 1 { "line" } else { "lines" };
+    writeln!(w, "... {} unchanged {noun} ...", grouped(hidden))
+}
+
+/// Formats `n` with its digits in groups of three, as `1.234.567`.
+fn grouped(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Writes every action of `diff` as a line numbered in the old and the new
+/// file, colored with `theme`.
+///
+/// Every group of changes starts with a line giving the old and the new
+/// lines it spans, like `@@ -4,5 +4,6 @@`. With `context` set, only that
+/// many unchanged lines are shown around each change and the rest are
+/// collapsed into a single separator line, and the groups include the shown
+/// unchanged lines. Changes ignored by `filter` are shown like unchanged
+/// lines and don't count as changes. Lines that `moved` marks are painted
+/// with the moved style of `theme` instead of the added or removed one. With
+/// `headings`, the lines that start the groups end with the heading each
+/// group falls under.
+///
+/// Lines that `highlighted` has spans for keep their syntax highlighting,
+/// with a background instead of the theme marking them as added or removed.
+#[allow(clippy::too_many_arguments)]
+pub fn write<W: Write>(
+    w: &mut W,
+    diff: &Diff<&str>,
+    context: Option<usize>,
+    filter: &Filter,
+    moved: Option<&Moved>,
+    headings: Option<&Headings>,
+    highlighted: Option<&Highlighted>,
+    theme: &Theme,
+) -> io::Result<()> {
+    let actions = diff.actions;
+    let painter = Painter {
+        theme,
+        moved,
+        highlighted,
+    };
+    let width = f32::log10(actions.len() as f32) as usize + 1;
+    let significant = filter.significant(diff);
+    // Without context every line is shown, and each run of changes is a
+    // group of its own.
+    let groups = hunk::groups(&significant, context.unwrap_or(0));
+    let visible = match context {
+        Some(_) => {
+            let mut visible = vec![false; actions.len()];
+            for group in &groups {
+                visible[group.clone()].fill(true);
+            }
+            visible
+        }
+        None => vec![true; actions.len()],
+    };
+    let mut groups = groups.into_iter().peekable();
+    let mut hidden = 0;
+    for (i, action) in actions.iter().enumerate() {
+        if !visible[i] {
+            hidden += 1;
+            continue;
+        }
+        if hidden > 0 {
+            write_collapsed(w, hidden)?;
+            hidden = 0;
+        }
+        if let Some(group) = groups.next_if(|group| group.start == i) {
+            write_header(w, diff, &actions[group], headings, theme)?;
+        }
+        write_action(w, diff, action, width, significant[i], &painter)?;
+    }
+    if hidden > 0 {
+        write_collapsed(w, hidden)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! Two-column output, as produced by `diff -y`.
+
+use std::io::{self, Write};
+
+use crate::{Action, Diff};
+
+/// Truncates or pads `text` to exactly `width` characters.
+fn column(text: &str, width: usize) -> String {
+    let mut result = text.chars().take(width).collect::<String>();
+    let len = result.chars().count();
+    result.extend(std::iter::repeat_n(' ', width - len));
+    result
+}
+
+/// Writes the old lines of `diff` on the left and the new lines on the
+/// right, using at most `width` columns per output line.
+///
+/// The gutter between the columns marks changed lines with `|`, removed
+/// lines with `<` and added lines with `>`.
+pub fn write<W: Write>(w: &mut W, diff: &Diff<&str>, width: usize) -> io::Result<()> {
+    let col = width.saturating_sub(3) / 2;
+    for action in diff.actions {
+        let (left, marker, right) = match *action {
+            Action::Add(_, j) => ("", '>', diff.new[j]),
+            Action::Remove(i, _) => (diff.old[i], '<', ""),
+            Action::Substitute(i, j) => (diff.old[i], '|', diff.new[j]),
+            Action::Ignore(i, j) => (diff.old[i], ' ', diff.new[j]),
+        };
+        let left = column(left, col);
+        let right = right.chars().take(col).collect::<String>();
+        writeln!(w, "{}", format!("{left} {marker} {right}").trim_end())?;
+    }
+    Ok(())
+}
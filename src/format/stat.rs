@@ -0,0 +1,120 @@
+//! Summary of how many lines each file changes, like `git diff --stat`.
+
+use std::io::{self, Write};
+
+use super::Theme;
+use crate::{filter::Filter, Action, Diff};
+
+/// The number of changed lines of a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stat {
+    /// Lines only present in the new file.
+    pub added: usize,
+    /// Lines only present in the old file.
+    pub removed: usize,
+    /// Lines replaced by another line.
+    pub substituted: usize,
+}
+
+impl Stat {
+    /// Counts the changes of `diff` that are not ignored by `filter`.
+    pub fn new(diff: &Diff<&str>, filter: &Filter) -> Self {
+        let mut stat = Stat::default();
+        for (action, significant) in diff.actions.iter().zip(filter.significant(diff)) {
+            match action {
+                _ if !significant => {}
+                Action::Add(..) => stat.added += 1,
+                Action::Remove(..) => stat.removed += 1,
+                Action::Substitute(..) => stat.substituted += 1,
+                Action::Ignore(..) => {}
+            }
+        }
+        stat
+    }
+
+    fn total(&self) -> usize {
+        self.added + self.removed + self.substituted
+    }
+}
+
+/// Scales `count` to a bar of at most `width` columns for a file changing
+/// `max` lines, keeping at least one column for any change.
+fn scale(count: usize, width: usize, max: usize) -> usize {
+    match count {
+        0 => 0,
+        _ if max <= width => count,
+        _ => 1 + count * (width - 1) / max,
+    }
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    match count {
+        1 => format!("{count} {singular}"),
+        _ => format!("{count} {plural}"),
+    }
+}
+
+/// Writes one line per file with its number of changed lines and a bar of
+/// `+`, `-` and `~` scaled to fit in `width` columns, followed by a total.
+///
+/// Binary files, given as `None`, are shown as `Bin`. Nothing is written
+/// when `files` is empty.
+pub fn write<W: Write>(
+    w: &mut W,
+    files: &[(String, Option<Stat>)],
+    width: usize,
+    theme: &Theme,
+) -> io::Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let name_width = files.iter().map(|(name, _)| name.chars().count()).max();
+    let name_width = name_width.unwrap_or(0);
+    let totals = files
+        .iter()
+        .flat_map(|(_, stat)| stat.map(|stat| stat.total()));
+    let max = totals.max().unwrap_or(0);
+    let count_width = max.to_string().len().max(3);
+    let bar_width = width.saturating_sub(name_width + count_width + 5).max(10);
+    let mut total = Stat::default();
+    for (name, stat) in files {
+        let Some(stat) = stat else {
+            writeln!(w, " {name:<name_width$} | {:>count_width$}", "Bin")?;
+            continue;
+        };
+        total.added += stat.added;
+        total.removed += stat.removed;
+        total.substituted += stat.substituted;
+        let mut bar = String::new();
+        for (count, marker, style) in [
+            (stat.added, "+", theme.add),
+            (stat.removed, "-", theme.remove),
+            (stat.substituted, "~", theme.substitute),
+        ] {
+            let len = scale(count, bar_width, max);
+            if len > 0 {
+                bar.push_str(&style.paint(marker.repeat(len)).to_string());
+            }
+        }
+        writeln!(
+            w,
+            " {name:<name_width$} | {:>count_width$} {bar}",
+            stat.total()
+        )?;
+    }
+    let mut summary = vec![plural(files.len(), "file changed", "files changed")];
+    if total.added > 0 {
+        summary.push(plural(total.added, "insertion(+)", "insertions(+)"));
+    }
+    if total.removed > 0 {
+        summary.push(plural(total.removed, "deletion(-)", "deletions(-)"));
+    }
+    if total.substituted > 0 {
+        summary.push(plural(
+            total.substituted,
+            "substitution(~)",
+            "substitutions(~)",
+        ));
+    }
+    writeln!(w, " {}", summary.join(", "))
+}
@@ -0,0 +1,76 @@
+//! Listing of the differences between two parsed documents.
+
+use std::io::{self, Write};
+
+use serde_json::Value;
+
+use super::Theme;
+use crate::structure::{Change, Location};
+
+/// How the locations and the values of changes are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    /// Locations as `jq` paths such as `.spec.ports[0]`, and values as JSON.
+    #[default]
+    Jq,
+    /// Locations as dotted paths such as `server.port`, and values as JSON,
+    /// as suits TOML.
+    Dotted,
+    /// Locations as dotted paths, and strings without quotes, as suits INI
+    /// and `.env` files, whose values are all strings.
+    Text,
+}
+
+impl Notation {
+    fn location(self, location: &Location) -> String {
+        match self {
+            Notation::Jq => location.to_string(),
+            Notation::Dotted | Notation::Text => location.dotted(),
+        }
+    }
+
+    fn value(self, value: &Value) -> String {
+        match (self, value) {
+            (Notation::Text, Value::String(text)) => text.clone(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// Writes every change on a line of its own, with its location and values
+/// in `notation`, colored and marked with `theme`.
+pub fn write<W: Write>(
+    w: &mut W,
+    changes: &[Change],
+    notation: Notation,
+    theme: &Theme,
+) -> io::Result<()> {
+    for change in changes {
+        match change {
+            Change::Added(location, value) => writeln!(
+                w,
+                "{} {}: {}",
+                theme.add.paint(&theme.markers.add),
+                notation.location(location),
+                theme.add.paint(notation.value(value)),
+            )?,
+            Change::Removed(location, value) => writeln!(
+                w,
+                "{} {}: {}",
+                theme.remove.paint(&theme.markers.remove),
+                notation.location(location),
+                theme.remove.paint(notation.value(value)),
+            )?,
+            Change::Changed(location, old, new) => writeln!(
+                w,
+                "{} {}: {} {} {}",
+                theme.substitute.paint(&theme.markers.substitute),
+                notation.location(location),
+                theme.remove.paint(notation.value(old)),
+                theme.markers.to,
+                theme.add.paint(notation.value(new)),
+            )?,
+        }
+    }
+    Ok(())
+}
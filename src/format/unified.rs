@@ -0,0 +1,256 @@
+//! Unified diff output, as produced by `diff -u`.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    mem,
+    rc::Rc,
+};
+
+use crate::{
+    filter::Filter,
+    heading::{self, Headings},
+    Action, Diff,
+};
+
+struct Line<'a> {
+    tag: char,
+    significant: bool,
+    text: &'a str,
+    old: usize,
+    new: usize,
+}
+
+/// Flattens the edit script of `diff` into unified diff lines.
+///
+/// Within a run of changes every removed line is emitted before the added
+/// ones, so a `Substitute` is split into a `-` and a `+` line.
+fn flatten<'a>(diff: &Diff<&'a str>, filter: &Filter) -> Vec<Line<'a>> {
+    let significant = filter.significant(diff);
+    let mut lines = Vec::with_capacity(diff.actions.len());
+    let mut removed = vec![];
+    let mut added = vec![];
+    for (action, &significant) in diff.actions.iter().zip(&significant) {
+        let line = |tag, text, (old, new)| Line {
+            tag,
+            significant,
+            text,
+            old,
+            new,
+        };
+        match *action {
+            Action::Add(i, j) => added.push(line('+', diff.new[j], (i, j))),
+            Action::Remove(i, j) => removed.push(line('-', diff.old[i], (i, j))),
+            Action::Substitute(i, j) => {
+                removed.push(line('-', diff.old[i], (i, j)));
+                added.push(line('+', diff.new[j], (i, j)));
+            }
+            Action::Ignore(i, j) => {
+                lines.append(&mut removed);
+                lines.append(&mut added);
+                lines.push(line(' ', diff.old[i], (i, j)));
+            }
+        }
+    }
+    lines.append(&mut removed);
+    lines.append(&mut added);
+    lines
+}
+
+/// Formats the lines `start..start + count` the way hunk headers give them.
+pub(super) fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{count}", start + 1),
+    }
+}
+
+/// Writes the edit script of `diff` as a unified diff with `context` lines
+/// of context around each change. Hunks made up of changes ignored by
+/// `filter` are left out.
+///
+/// `newlines` tells whether the old and the new file end with a newline;
+/// the last line of one that doesn't is marked like `diff -u` does. With
+/// `headings`, every hunk header ends with the heading the hunk falls under.
+#[allow(clippy::too_many_arguments)]
+pub fn write<W: Write>(
+    w: &mut W,
+    old_name: &str,
+    new_name: &str,
+    diff: &Diff<&str>,
+    context: usize,
+    filter: &Filter,
+    newlines: (bool, bool),
+    headings: Option<&Headings>,
+) -> io::Result<()> {
+    let last = |ends_with_newline: bool, len: usize| match ends_with_newline {
+        true => None,
+        false => len.checked_sub(1),
+    };
+    let mut writer = Writer::new(w, old_name, new_name, context, filter)
+        .no_newline_at(
+            last(newlines.0, diff.old.len()),
+            last(newlines.1, diff.new.len()),
+        )
+        .headings(headings);
+    writer.write(diff, (0, 0))?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// A line of the hunk being built.
+struct Pending {
+    tag: char,
+    text: String,
+    old: usize,
+    new: usize,
+    /// The last heading among the old lines before this one.
+    heading: Option<Rc<str>>,
+}
+
+/// Writes a unified diff of an edit script that arrives in pieces, holding
+/// on to the lines of one hunk at a time.
+pub struct Writer<'a, W> {
+    w: &'a mut W,
+    names: Option<(&'a str, &'a str)>,
+    context: usize,
+    filter: &'a Filter,
+    /// Up to `context` unchanged lines since the last hunk.
+    before: VecDeque<Pending>,
+    /// The hunk being built, if it has any changes.
+    hunk: Vec<Pending>,
+    /// The number of unchanged lines at the end of `hunk`.
+    trailing: usize,
+    /// The old and the new line, if any, that has no newline after it.
+    no_newline: (Option<usize>, Option<usize>),
+    headings: Option<&'a Headings>,
+    /// The last heading among the old lines so far.
+    heading: Option<Rc<str>>,
+}
+
+impl<'a, W: Write> Writer<'a, W> {
+    /// Starts a unified diff with `context` lines of context, leaving out
+    /// changes ignored by `filter`. The file headers are only written with
+    /// the first hunk.
+    pub fn new(
+        w: &'a mut W,
+        old_name: &'a str,
+        new_name: &'a str,
+        context: usize,
+        filter: &'a Filter,
+    ) -> Self {
+        Writer {
+            w,
+            names: Some((old_name, new_name)),
+            context,
+            filter,
+            before: VecDeque::new(),
+            hunk: vec![],
+            trailing: 0,
+            no_newline: (None, None),
+            headings: None,
+            heading: None,
+        }
+    }
+
+    /// Marks the line `old` of the old file and `new` of the new file as
+    /// missing the newline after them, as the last line of a file can be.
+    pub fn no_newline_at(mut self, old: Option<usize>, new: Option<usize>) -> Self {
+        self.no_newline = (old, new);
+        self
+    }
+
+    /// Ends every hunk header with the heading that `headings` finds
+    /// before the hunk, if there is one.
+    pub fn headings(mut self, headings: Option<&'a Headings>) -> Self {
+        self.headings = headings;
+        self
+    }
+
+    /// Adds the edit script of `diff`, whose sequences start after `offset`
+    /// old and new lines of the whole files, and writes the hunks it
+    /// completes.
+    pub fn write(&mut self, diff: &Diff<&str>, offset: (usize, usize)) -> io::Result<()> {
+        for line in flatten(diff, self.filter) {
+            let pending = Pending {
+                tag: line.tag,
+                text: line.text.to_string(),
+                old: offset.0 + line.old,
+                new: offset.1 + line.new,
+                heading: self.heading.clone(),
+            };
+            if let Some(headings) = self.headings {
+                if line.tag != '+' && headings.matches(line.text) {
+                    self.heading = Some(heading::shorten(line.text).into());
+                }
+            }
+            if line.significant {
+                if self.hunk.is_empty() {
+                    self.hunk.extend(self.before.drain(..));
+                }
+                self.hunk.push(pending);
+                self.trailing = 0;
+            } else if !self.hunk.is_empty() {
+                self.hunk.push(pending);
+                self.trailing += 1;
+                // Changes further apart than twice the context go into
+                // separate hunks.
+                if self.trailing > 2 * self.context {
+                    let rest = self
+                        .hunk
+                        .split_off(self.hunk.len() - self.trailing + self.context);
+                    self.write_hunk()?;
+                    self.before.extend(rest.into_iter().skip(1));
+                }
+            } else {
+                self.before.push_back(pending);
+                if self.before.len() > self.context {
+                    self.before.pop_front();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the last hunk and returns whether anything was written.
+    pub fn finish(mut self) -> io::Result<bool> {
+        if !self.hunk.is_empty() {
+            let len = self.hunk.len() - self.trailing + self.trailing.min(self.context);
+            self.hunk.truncate(len);
+            self.write_hunk()?;
+        }
+        Ok(self.names.is_none())
+    }
+
+    fn write_hunk(&mut self) -> io::Result<()> {
+        let w = &mut *self.w;
+        if let Some((old_name, new_name)) = self.names.take() {
+            writeln!(w, "--- {old_name}")?;
+            writeln!(w, "+++ {new_name}")?;
+        }
+        let hunk = mem::take(&mut self.hunk);
+        let old_count = hunk.iter().filter(|line| line.tag != '+').count();
+        let new_count = hunk.iter().filter(|line| line.tag != '-').count();
+        write!(
+            w,
+            "@@ -{} +{} @@",
+            range(hunk[0].old, old_count),
+            range(hunk[0].new, new_count),
+        )?;
+        match &hunk[0].heading {
+            Some(heading) => writeln!(w, " {heading}")?,
+            None => writeln!(w)?,
+        }
+        for line in hunk {
+            writeln!(w, "{}{}", line.tag, line.text)?;
+            let (old, new) = self.no_newline;
+            if line.tag != '+' && old == Some(line.old) || line.tag != '-' && new == Some(line.new)
+            {
+                writeln!(w, "\\ No newline at end of file")?;
+            }
+        }
+        self.trailing = 0;
+        Ok(())
+    }
+}
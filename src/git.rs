@@ -0,0 +1,30 @@
+//! Reading file contents from a git repository.
+
+use std::path::Path;
+
+use git2::Repository;
+
+/// Returns the content `path` had at revision `rev` of the repository that
+/// contains it.
+///
+/// `rev` is any revision git understands, such as `HEAD~1` or a commit
+/// hash, and `path` is relative to the current directory.
+pub fn read_at_revision(path: &Path, rev: &str) -> Result<Vec<u8>, git2::Error> {
+    let path = std::path::absolute(path).map_err(|err| git2::Error::from_str(&err.to_string()))?;
+    let repo = Repository::discover(path.parent().unwrap_or(&path))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("repository has no working directory"))?;
+    let workdir = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_owned());
+    let dir = path.parent().unwrap_or(&path);
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_owned());
+    let relative = dir
+        .strip_prefix(&workdir)
+        .map_err(|_| git2::Error::from_str("path is outside of the repository"))?
+        .join(path.file_name().unwrap_or_default());
+    let tree = repo.revparse_single(rev)?.peel_to_tree()?;
+    let blob = tree.get_path(&relative)?.to_object(&repo)?.peel_to_blob()?;
+    Ok(blob.content().to_vec())
+}
@@ -0,0 +1,67 @@
+//! Finding the heading, such as a function signature, that a line falls
+//! under.
+
+use regex::Regex;
+
+/// Words that start a definition on an indented line, such as a method.
+const DEFINITIONS: &[&str] = &[
+    "async ",
+    "class ",
+    "def ",
+    "fn ",
+    "func ",
+    "function ",
+    "impl ",
+    "interface ",
+    "pub ",
+    "struct ",
+    "trait ",
+];
+
+/// The most characters of a heading shown after a hunk header, as with
+/// GNU diff.
+const MAX_LEN: usize = 40;
+
+/// How heading lines are recognized.
+#[derive(Clone, Debug, Default)]
+pub enum Headings {
+    /// A heuristic for common languages: unindented lines that start with
+    /// a letter, `_` or `$` like with `diff -p`, section lines such as
+    /// `[section]`, and indented lines that start a definition.
+    #[default]
+    Builtin,
+    /// Lines that match one of these patterns.
+    Matching(Vec<Regex>),
+}
+
+impl Headings {
+    /// Returns whether `line` is a heading.
+    pub fn matches(&self, line: &str) -> bool {
+        match self {
+            Headings::Builtin => {
+                let trimmed = line.trim_start();
+                if trimmed.len() < line.len() {
+                    return DEFINITIONS.iter().any(|word| trimmed.starts_with(word));
+                }
+                line.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '$')
+                    || line.starts_with('[')
+            }
+            Headings::Matching(regexes) => regexes.iter().any(|regex| regex.is_match(line)),
+        }
+    }
+
+    /// Returns the last heading among `lines`, shortened for a hunk header.
+    pub fn last<'a>(&self, lines: &[&'a str]) -> Option<&'a str> {
+        let line = lines.iter().rev().find(|line| self.matches(line))?;
+        Some(shorten(line))
+    }
+}
+
+/// Shortens a heading line for a hunk header.
+pub fn shorten(line: &str) -> &str {
+    let end = line
+        .char_indices()
+        .nth(MAX_LEN)
+        .map_or(line.len(), |(i, _)| i);
+    line[..end].trim_end()
+}
@@ -0,0 +1,88 @@
+//! Hirschberg's linear-space Levenshtein alignment.
+
+use crate::{edit::Edit, Costs};
+
+/// Returns the last row of the Levenshtein matrix of `s1` against `s2`, or
+/// of their reversals when `rev` is set.
+pub(crate) fn last_row<T: Eq>(s1: &[T], s2: &[T], rev: bool, costs: &Costs) -> Vec<usize> {
+    let m = s2.len();
+    let at = |s: &[T], i: usize| if rev { s.len() - 1 - i } else { i };
+    let mut prev = (0..=m).map(|j| j * costs.insert).collect::<Vec<_>>();
+    let mut cur = vec![0; m + 1];
+    for i in 0..s1.len() {
+        let x = &s1[at(s1, i)];
+        cur[0] = (i + 1) * costs.delete;
+        for j in 1..=m {
+            let cost = match *x == s2[at(s2, j - 1)] {
+                true => 0,
+                false => costs.substitute,
+            };
+            cur[j] = (prev[j] + costs.delete)
+                .min(cur[j - 1] + costs.insert)
+                .min(prev[j - 1] + cost);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev
+}
+
+fn align<T: Eq>(s1: &[T], s2: &[T], (o1, o2): (usize, usize), costs: &Costs, out: &mut Vec<Edit>) {
+    if s1.is_empty() {
+        out.extend((0..s2.len()).map(|j| Edit::Insert(o2 + j)));
+        return;
+    }
+    if s2.is_empty() {
+        out.extend((0..s1.len()).map(|i| Edit::Delete(o1 + i)));
+        return;
+    }
+    if s1.len() == 1 {
+        let (j, edit) = match s2.iter().position(|x| *x == s1[0]) {
+            Some(j) => (j, Edit::Equal(o1, o2 + j)),
+            None if costs.substitute <= costs.delete + costs.insert => {
+                (0, Edit::Substitute(o1, o2))
+            }
+            None => {
+                out.push(Edit::Delete(o1));
+                out.extend((0..s2.len()).map(|j| Edit::Insert(o2 + j)));
+                return;
+            }
+        };
+        out.extend((0..j).map(|j| Edit::Insert(o2 + j)));
+        out.push(edit);
+        out.extend((j + 1..s2.len()).map(|j| Edit::Insert(o2 + j)));
+        return;
+    }
+    let mid = s1.len() / 2;
+    let left = last_row(&s1[..mid], s2, false, costs);
+    let right = last_row(&s1[mid..], s2, true, costs);
+    let split = (0..=s2.len())
+        .min_by_key(|&j| left[j] + right[s2.len() - j])
+        .unwrap();
+    align(&s1[..mid], &s2[..split], (o1, o2), costs, out);
+    align(&s1[mid..], &s2[split..], (o1 + mid, o2 + split), costs, out);
+}
+
+/// Computes a minimal Levenshtein edit script in O(min(n, m)) memory.
+pub(crate) fn edits<T: Eq>(s1: &[T], s2: &[T], costs: &Costs) -> Vec<Edit> {
+    let mut result = Vec::with_capacity(s1.len().max(s2.len()));
+    if s2.len() <= s1.len() {
+        align(s1, s2, (0, 0), costs, &mut result);
+        return result;
+    }
+    // Aligning the other way around turns insertions into deletions.
+    let swapped = Costs {
+        insert: costs.delete,
+        delete: costs.insert,
+        ..*costs
+    };
+    align(s2, s1, (0, 0), &swapped, &mut result);
+    result
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal(j, i) => Edit::Equal(i, j),
+            Edit::Delete(j) => Edit::Insert(j),
+            Edit::Insert(i) => Edit::Delete(i),
+            Edit::Substitute(j, i) => Edit::Substitute(i, j),
+        })
+        .collect()
+}
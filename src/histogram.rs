@@ -0,0 +1,128 @@
+//! Histogram diff, the default of git, which anchors on the longest common
+//! run of the rarest items before diffing the parts around it.
+//!
+//! This is a generalization of patience diff: instead of only items that
+//! occur once on both sides, it picks the items that occur the fewest times
+//! in the old input, so it still finds anchors in files full of repeats.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    edit::{self, Edit},
+    hook::DiffHook,
+    myers, trim, Action,
+};
+
+/// Items occurring more often than this in the old input are not used as
+/// anchors, as in git.
+const MAX_CHAIN: usize = 64;
+
+/// A common run of items, `len` long and starting at `old` and `new`.
+#[derive(Clone, Copy)]
+struct Region {
+    old: usize,
+    new: usize,
+    len: usize,
+    /// The fewest times an item of the run occurs in the old input.
+    count: usize,
+}
+
+/// Returns the common run of `s1` and `s2` that contains the rarest items,
+/// preferring longer runs between equally rare ones.
+fn find_region<T: Hash + Eq>(s1: &[T], s2: &[T]) -> Option<Region> {
+    let mut occurrences = HashMap::<&T, Vec<usize>>::new();
+    for (i, item) in s1.iter().enumerate() {
+        occurrences.entry(item).or_default().push(i);
+    }
+    let count = |item: &T| occurrences.get(item).map_or(0, Vec::len);
+    let mut best: Option<Region> = None;
+    let mut j = 0;
+    while j < s2.len() {
+        let mut next = j + 1;
+        let positions = occurrences.get(&s2[j]).filter(|positions| {
+            positions.len() <= MAX_CHAIN && best.is_none_or(|best| positions.len() <= best.count)
+        });
+        for &i in positions.into_iter().flatten() {
+            let (mut start1, mut start2) = (i, j);
+            while start1 > 0 && start2 > 0 && s1[start1 - 1] == s2[start2 - 1] {
+                start1 -= 1;
+                start2 -= 1;
+            }
+            let (mut end1, mut end2) = (i + 1, j + 1);
+            while end1 < s1.len() && end2 < s2.len() && s1[end1] == s2[end2] {
+                end1 += 1;
+                end2 += 1;
+            }
+            let region = Region {
+                old: start1,
+                new: start2,
+                len: end1 - start1,
+                count: s1[start1..end1].iter().map(count).min().unwrap_or(0),
+            };
+            let better = best.is_none_or(|best| {
+                region.count < best.count || region.count == best.count && region.len > best.len
+            });
+            if better {
+                best = Some(region);
+            }
+            // The rest of this run was just looked at.
+            next = next.max(end2);
+        }
+        j = next;
+    }
+    best
+}
+
+/// Appends the edit script of `s1` and `s2`, which start at `offset` in the
+/// whole inputs, to `out`.
+fn align<T: Hash + Eq>(s1: &[T], s2: &[T], (o1, o2): (usize, usize), out: &mut Vec<Edit>) {
+    let (prefix, suffix) = trim::common(s1, s2);
+    out.extend((0..prefix).map(|k| Edit::Equal(o1 + k, o2 + k)));
+    let (end1, end2) = (s1.len() - suffix, s2.len() - suffix);
+    let (m1, m2) = (&s1[prefix..end1], &s2[prefix..end2]);
+    let (o1, o2) = (o1 + prefix, o2 + prefix);
+    if !m1.is_empty() && !m2.is_empty() {
+        match find_region(m1, m2) {
+            Some(Region { old, new, len, .. }) => {
+                align(&m1[..old], &m2[..new], (o1, o2), out);
+                out.extend((0..len).map(|k| Edit::Equal(o1 + old + k, o2 + new + k)));
+                let (after1, after2) = (old + len, new + len);
+                align(
+                    &m1[after1..],
+                    &m2[after2..],
+                    (o1 + after1, o2 + after2),
+                    out,
+                );
+            }
+            // Only items too common to anchor on: fall back to a plain
+            // minimal diff.
+            None => {
+                let edits = myers::edits(m1, m2);
+                out.extend(edits.into_iter().map(|edit| edit.shift(o1, o2)));
+            }
+        }
+    } else {
+        out.extend((0..m1.len()).map(|i| Edit::Delete(o1 + i)));
+        out.extend((0..m2.len()).map(|j| Edit::Insert(o2 + j)));
+    }
+    out.extend((0..suffix).map(|k| Edit::Equal(o1 + m1.len() + k, o2 + m2.len() + k)));
+}
+
+/// Computes the edit script that turns `s1` into `s2` with the histogram
+/// algorithm.
+///
+/// Like [`patience`](crate::patience), the result is not always minimal but
+/// tends to read better for source code, and neighbouring removals and
+/// additions are reported as [`Action::Substitute`].
+pub fn histogram<T: Hash + Eq>(s1: &[T], s2: &[T]) -> Vec<Action> {
+    let mut actions = vec![];
+    histogram_with(s1, s2, &mut actions);
+    actions
+}
+
+/// Like [`histogram`], but passes the edit script to `hook` instead.
+pub(crate) fn histogram_with<T: Hash + Eq, H: DiffHook + ?Sized>(s1: &[T], s2: &[T], hook: &mut H) {
+    let mut edits = Vec::with_capacity(s1.len().max(s2.len()));
+    align(s1, s2, (0, 0), &mut edits);
+    edit::replay(&edits, true, hook);
+}
@@ -0,0 +1,71 @@
+//! Visiting an edit script step by step instead of collecting it.
+
+use crate::Action;
+
+/// Receives the steps of an edit script in order, as the 0-based `(old,
+/// new)` indices of [`Action`].
+///
+/// Every method does nothing by default, so implementations only handle
+/// the steps they care about.
+pub trait DiffHook {
+    /// The old item at `old` is kept as the new item at `new`.
+    fn on_equal(&mut self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    /// The new item at `new` is inserted before the old item at `old`.
+    fn on_insert(&mut self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    /// The old item at `old` is deleted before the new item at `new`.
+    fn on_delete(&mut self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    /// The old item at `old` is replaced by the new item at `new`.
+    fn on_replace(&mut self, old: usize, new: usize) {
+        let _ = (old, new);
+    }
+
+    /// Called once after the last step.
+    fn finish(&mut self) {}
+}
+
+/// Collects the steps as actions.
+impl DiffHook for Vec<Action> {
+    fn on_equal(&mut self, old: usize, new: usize) {
+        self.push(Action::Ignore(old, new));
+    }
+
+    fn on_insert(&mut self, old: usize, new: usize) {
+        self.push(Action::Add(old, new));
+    }
+
+    fn on_delete(&mut self, old: usize, new: usize) {
+        self.push(Action::Remove(old, new));
+    }
+
+    fn on_replace(&mut self, old: usize, new: usize) {
+        self.push(Action::Substitute(old, new));
+    }
+}
+
+/// Passes a single action to `hook`.
+pub(crate) fn visit<H: DiffHook + ?Sized>(hook: &mut H, action: &Action) {
+    match *action {
+        Action::Add(i, j) => hook.on_insert(i, j),
+        Action::Remove(i, j) => hook.on_delete(i, j),
+        Action::Substitute(i, j) => hook.on_replace(i, j),
+        Action::Ignore(i, j) => hook.on_equal(i, j),
+    }
+}
+
+/// Walks an existing edit script, calling `hook` for every action and
+/// finishing it at the end.
+pub fn replay<H: DiffHook + ?Sized>(actions: &[Action], hook: &mut H) {
+    for action in actions {
+        visit(hook, action);
+    }
+    hook.finish();
+}
@@ -0,0 +1,67 @@
+//! Grouping an edit script into hunks of nearby changes.
+
+use std::{borrow::Cow, ops::Range};
+
+use crate::Action;
+
+/// A run of nearby changes along with the unchanged items around them.
+///
+/// The actions are borrowed from the whole edit script, except for hunks
+/// that were deserialized, which own them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hunk<'a> {
+    /// The 0-based indices of the old items the hunk covers.
+    pub old_range: Range<usize>,
+    /// The 0-based indices of the new items the hunk covers.
+    pub new_range: Range<usize>,
+    /// The actions of the hunk, in order.
+    pub actions: Cow<'a, [Action]>,
+}
+
+/// Groups the set flags of `flags` into half-open index ranges that include
+/// up to `context` other indices on either side. Ranges that would touch or
+/// overlap are merged.
+pub(crate) fn groups(flags: &[bool], context: usize) -> Vec<Range<usize>> {
+    let mut groups: Vec<Range<usize>> = vec![];
+    for (i, _) in flags.iter().enumerate().filter(|(_, &flag)| flag) {
+        let start = i.saturating_sub(context);
+        let end = (i + 1 + context).min(flags.len());
+        match groups.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => groups.push(start..end),
+        }
+    }
+    groups
+}
+
+/// Groups the changes of `actions` into hunks with up to `context`
+/// unchanged items on either side, as in a unified diff.
+pub fn hunks(actions: &[Action], context: usize) -> Vec<Hunk<'_>> {
+    let changed = actions.iter().map(Action::is_change).collect::<Vec<_>>();
+    groups(&changed, context)
+        .into_iter()
+        .map(|range| {
+            let actions = &actions[range];
+            let (old, new) = match actions[0] {
+                Action::Add(i, j)
+                | Action::Remove(i, j)
+                | Action::Substitute(i, j)
+                | Action::Ignore(i, j) => (i, j),
+            };
+            let old_len = actions
+                .iter()
+                .filter(|action| !matches!(action, Action::Add(..)))
+                .count();
+            let new_len = actions
+                .iter()
+                .filter(|action| !matches!(action, Action::Remove(..)))
+                .count();
+            Hunk {
+                old_range: old..old + old_len,
+                new_range: new..new + new_len,
+                actions: Cow::Borrowed(actions),
+            }
+        })
+        .collect()
+}
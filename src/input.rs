@@ -0,0 +1,307 @@
+//! Inspecting and decoding raw input bytes.
+
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Read},
+    path::Path,
+};
+
+/// How many leading bytes are searched for a NUL byte, as git does.
+const SNIFF_LEN: usize = 8000;
+
+/// Returns `true` if `bytes` look like binary rather than text, that is they
+/// contain a NUL byte near the start.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// A text encoding that inputs can be decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, the only encoding the rest of the crate deals with.
+    Utf8,
+    /// UTF-16, little-endian.
+    Utf16Le,
+    /// UTF-16, big-endian.
+    Utf16Be,
+    /// ISO 8859-1, where every byte is the character of the same number.
+    Latin1,
+}
+
+impl Encoding {
+    /// Guesses the encoding of `bytes`.
+    ///
+    /// UTF-16 is recognized by its byte order mark, or by NUL bytes in every
+    /// other position as mostly ASCII text has. Input that is not valid
+    /// UTF-8 but has none of the control characters that are rare in text
+    /// is taken to be Latin-1. Anything else is UTF-8, and invalid bytes are
+    /// escaped by [`decode`].
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xff, 0xfe, ..] => return Encoding::Utf16Le,
+            [0xfe, 0xff, ..] => return Encoding::Utf16Be,
+            _ => {}
+        }
+        let sniffed = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+        if !sniffed.is_empty() {
+            let zeros = |offset: usize| {
+                sniffed
+                    .iter()
+                    .skip(offset)
+                    .step_by(2)
+                    .filter(|&&b| b == 0)
+                    .count()
+            };
+            let pairs = sniffed.len() / 2;
+            // Most characters of UTF-16 text in a Latin script have a zero
+            // high byte, and a NUL is rare in the low byte.
+            match (zeros(0), zeros(1)) {
+                (0, odd) if odd * 4 >= pairs * 3 => return Encoding::Utf16Le,
+                (even, 0) if even * 4 >= pairs * 3 => return Encoding::Utf16Be,
+                _ => {}
+            }
+        }
+        let control = |&b: &u8| b == 0 || (0x80..0xa0).contains(&b);
+        match std::str::from_utf8(bytes) {
+            Err(_) if !bytes.iter().any(control) => Encoding::Latin1,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// Converts `bytes` from this encoding to UTF-8, dropping the byte order
+    /// mark of UTF-16.
+    ///
+    /// Unpaired UTF-16 surrogates become U+FFFD, and UTF-8 is returned as is.
+    pub fn to_utf8<'a>(self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        let utf16 = |unit: fn([u8; 2]) -> u16| {
+            let units = bytes
+                .chunks(2)
+                .map(|pair| unit([pair[0], *pair.get(1).unwrap_or(&0)]));
+            let text = char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect::<String>();
+            let text = text.strip_prefix('\u{feff}').unwrap_or(&text).to_string();
+            Cow::Owned(text.into_bytes())
+        };
+        match self {
+            Encoding::Utf8 => Cow::Borrowed(bytes),
+            Encoding::Utf16Le => utf16(u16::from_le_bytes),
+            Encoding::Utf16Be => utf16(u16::from_be_bytes),
+            Encoding::Latin1 => Cow::Owned(
+                bytes
+                    .iter()
+                    .map(|&b| char::from(b))
+                    .collect::<String>()
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    /// Returns the offset of the first byte of `bytes` that is not valid in
+    /// this encoding, such as an unpaired UTF-16 surrogate, if there is one.
+    pub fn invalid_offset(self, bytes: &[u8]) -> Option<usize> {
+        let utf16 = |unit: fn([u8; 2]) -> u16| {
+            if bytes.len() % 2 == 1 {
+                return Some(bytes.len() - 1);
+            }
+            let units = bytes.chunks(2).map(|pair| unit([pair[0], pair[1]]));
+            let mut offset = 0;
+            for c in char::decode_utf16(units) {
+                match c {
+                    Ok(c) => offset += c.len_utf16() * 2,
+                    Err(_) => return Some(offset),
+                }
+            }
+            None
+        };
+        match self {
+            Encoding::Utf8 => std::str::from_utf8(bytes)
+                .err()
+                .map(|err| err.valid_up_to()),
+            Encoding::Utf16Le => utf16(u16::from_le_bytes),
+            Encoding::Utf16Be => utf16(u16::from_be_bytes),
+            Encoding::Latin1 => None,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => f.write_str("UTF-8"),
+            Encoding::Utf16Le => f.write_str("UTF-16LE"),
+            Encoding::Utf16Be => f.write_str("UTF-16BE"),
+            Encoding::Latin1 => f.write_str("Latin-1"),
+        }
+    }
+}
+
+/// A compression format that inputs are decompressed from before they are
+/// compared.
+///
+/// Zstandard and xz are only decompressed when the crate is built with the
+/// `zstd` and `xz` features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip, for `.gz` files.
+    Gzip,
+    /// Zstandard, for `.zst` files.
+    Zstd,
+    /// xz, for `.xz` files.
+    Xz,
+}
+
+impl Compression {
+    /// Returns the compression of the file at `path` by its extension.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Returns `path` without the extension of its compression, so that
+    /// the type of its content can be told by the extension before it.
+    pub fn strip(path: &Path) -> &Path {
+        match Compression::detect(path) {
+            Some(_) => Path::new(path.file_stem().unwrap_or_default()),
+            None => path,
+        }
+    }
+
+    /// Decompresses `bytes`.
+    pub fn decompress(self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        let mut result = vec![];
+        match self {
+            Compression::Gzip => {
+                flate2::read::MultiGzDecoder::new(bytes).read_to_end(&mut result)?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let invalid = |err: ruzstd::decoding::errors::FrameDecoderError| {
+                    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+                };
+                ruzstd::decoding::StreamingDecoder::new(bytes)
+                    .map_err(invalid)?
+                    .read_to_end(&mut result)?;
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz => {
+                lzma_rs::xz_decompress(&mut io::BufReader::new(bytes), &mut result)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+            }
+            #[allow(unreachable_patterns)]
+            compression => {
+                let message = format!("built without support for {compression}");
+                return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Gzip => f.write_str("gzip"),
+            Compression::Zstd => f.write_str("Zstandard"),
+            Compression::Xz => f.write_str("xz"),
+        }
+    }
+}
+
+/// How the lines of a text end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Every line ends with `\n`.
+    Lf,
+    /// Every line ends with `\r\n`.
+    Crlf,
+    /// Some lines end with `\n` and others with `\r\n`.
+    Mixed,
+}
+
+impl LineEnding {
+    /// Returns how the lines of `text` end, or `None` if it has no line
+    /// breaks.
+    pub fn detect(text: &str) -> Option<Self> {
+        let lines = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        match (lines, crlf) {
+            (0, _) => None,
+            (_, 0) => Some(LineEnding::Lf),
+            _ if crlf == lines => Some(LineEnding::Crlf),
+            _ => Some(LineEnding::Mixed),
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineEnding::Lf => f.write_str("LF"),
+            LineEnding::Crlf => f.write_str("CRLF"),
+            LineEnding::Mixed => f.write_str("mixed"),
+        }
+    }
+}
+
+/// Replaces every tab in `text` with the spaces that reach the next tab
+/// stop, with a tab stop every `width` characters.
+pub fn expand_tabs(text: &str, width: usize) -> Cow<'_, str> {
+    if !text.contains('\t') {
+        return Cow::Borrowed(text);
+    }
+    let width = width.max(1);
+    let mut result = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        match c {
+            '\t' => {
+                let spaces = width - column % width;
+                result.extend(std::iter::repeat_n(' ', spaces));
+                column += spaces;
+            }
+            '\n' => {
+                result.push(c);
+                column = 0;
+            }
+            _ => {
+                result.push(c);
+                column += 1;
+            }
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// The byte order mark that some editors put at the start of UTF-8 files.
+pub const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Renders arbitrary bytes as text without losing information.
+///
+/// Valid UTF-8 is kept as is, except that `\` becomes `\\`, and every other
+/// byte is written as `\xNN`.
+pub fn escape(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        result.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid() {
+            result.push_str(&format!("\\x{byte:02x}"));
+        }
+    }
+    result
+}
+
+/// Decodes two inputs consistently: as UTF-8 when both are valid, and
+/// through [`escape`] otherwise.
+pub fn decode<'a>(bytes1: &'a [u8], bytes2: &'a [u8]) -> (Cow<'a, str>, Cow<'a, str>) {
+    match (std::str::from_utf8(bytes1), std::str::from_utf8(bytes2)) {
+        (Ok(s1), Ok(s2)) => (Cow::Borrowed(s1), Cow::Borrowed(s2)),
+        _ => (Cow::Owned(escape(bytes1)), Cow::Owned(escape(bytes2))),
+    }
+}
@@ -0,0 +1,47 @@
+//! Replacing items by small integers so that they compare in constant time.
+
+use std::{collections::HashMap, hash::Hash};
+
+/// Two sequences with every distinct item replaced by the same id on both
+/// sides.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Interned {
+    /// The ids of the old items.
+    pub old: Vec<u32>,
+    /// The ids of the new items.
+    pub new: Vec<u32>,
+    /// The number of distinct items.
+    pub len: usize,
+}
+
+impl Interned {
+    /// Interns the items of `old` and `new`, numbering them in order of
+    /// first appearance.
+    pub fn new<T: Hash + Eq>(old: &[T], new: &[T]) -> Self {
+        let mut ids = HashMap::new();
+        let mut intern = |item| {
+            let next = ids.len() as u32;
+            *ids.entry(item).or_insert(next)
+        };
+        let old = old.iter().map(&mut intern).collect();
+        let new = new.iter().map(&mut intern).collect();
+        Interned {
+            old,
+            new,
+            len: ids.len(),
+        }
+    }
+
+    /// Returns how many times each id occurs in the old and in the new
+    /// sequence, indexed by id.
+    pub fn counts(&self) -> Vec<(usize, usize)> {
+        let mut counts = vec![(0, 0); self.len];
+        for &id in &self.old {
+            counts[id as usize].0 += 1;
+        }
+        for &id in &self.new {
+            counts[id as usize].1 += 1;
+        }
+        counts
+    }
+}
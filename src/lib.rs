@@ -0,0 +1,305 @@
+//! Line diffing based on the Levenshtein edit distance.
+//!
+//! The entry point is [`lev`], which computes the shortest edit script that
+//! turns one sequence into another as a list of [`Action`]s.
+
+#![allow(clippy::needless_range_loop)]
+
+pub mod archive;
+mod band;
+pub mod dir;
+mod edit;
+pub mod filter;
+pub mod format;
+pub mod git;
+pub mod heading;
+mod hirschberg;
+mod histogram;
+pub mod hook;
+pub mod hunk;
+pub mod input;
+pub mod intern;
+mod matrix;
+pub mod merge;
+pub mod moved;
+mod myers;
+pub mod normalize;
+pub mod notebook;
+pub mod options;
+pub mod patch;
+mod patience;
+pub mod stream;
+pub mod structure;
+#[cfg(feature = "syntect")]
+pub mod syntax;
+pub mod tokenize;
+pub mod trace;
+mod trim;
+
+pub use histogram::histogram;
+pub use hunk::{hunks, Hunk};
+pub use myers::myers;
+pub use patience::patience;
+
+use hook::DiffHook;
+use matrix::{Cell, Matrix, Step};
+use trace::Trace;
+
+/// Inputs whose cost matrix has more cells than this are aligned with
+/// Hirschberg's algorithm instead of the full matrix.
+const MATRIX_LIMIT: usize = 1 << 20;
+
+/// A single step of an edit script, as 0-based `(old, new)` indices into the
+/// old and new sequences.
+///
+/// For `Add` and `Remove`, the index into the sequence that lacks the item is
+/// where the item would go: the number of items of that sequence before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    /// The new item at the second index was inserted before the old item at
+    /// the first.
+    Add(usize, usize),
+    /// The old item at the first index was deleted before the new item at
+    /// the second.
+    Remove(usize, usize),
+    /// The old item at the first index was replaced by the new item at the
+    /// second.
+    Substitute(usize, usize),
+    /// The old item at the first index is kept as the new item at the
+    /// second.
+    Ignore(usize, usize),
+}
+
+impl Action {
+    /// Returns `true` for every action except [`Action::Ignore`].
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Action::Ignore(_, _))
+    }
+}
+
+/// An edit script along with the sequences it indexes into, which is what
+/// the renderers need to look up the items.
+#[derive(Clone, Copy, Debug)]
+pub struct Diff<'a, T> {
+    /// The old sequence.
+    pub old: &'a [T],
+    /// The new sequence.
+    pub new: &'a [T],
+    /// The edit script that turns `old` into `new`.
+    pub actions: &'a [Action],
+}
+
+impl<'a, T> Diff<'a, T> {
+    /// Returns the old and new item an action refers to, if any.
+    pub fn items(&self, action: &Action) -> (Option<&'a T>, Option<&'a T>) {
+        match *action {
+            Action::Add(_, j) => (None, Some(&self.new[j])),
+            Action::Remove(i, _) => (Some(&self.old[i]), None),
+            Action::Substitute(i, j) | Action::Ignore(i, j) => {
+                (Some(&self.old[i]), Some(&self.new[j]))
+            }
+        }
+    }
+}
+
+/// The cost of each kind of change, which [`lev_weighted`] minimizes the
+/// sum of.
+///
+/// The costs must be at most [`u32::MAX`], so that the total of an edit
+/// script can't overflow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Costs {
+    /// The cost of an [`Action::Add`].
+    pub insert: usize,
+    /// The cost of an [`Action::Remove`].
+    pub delete: usize,
+    /// The cost of an [`Action::Substitute`]. With a cost of at least an
+    /// insertion plus a deletion, changed items are never paired up.
+    pub substitute: usize,
+}
+
+impl Default for Costs {
+    /// Returns a cost of 1 for every change, the plain Levenshtein distance.
+    fn default() -> Self {
+        Costs {
+            insert: 1,
+            delete: 1,
+            substitute: 1,
+        }
+    }
+}
+
+/// Computes the edit script that turns `s1` into `s2`.
+///
+/// The returned actions are in order and cover every item of both inputs.
+/// Tracing the matrix or the path with [`trace::enable`] writes the final
+/// cost matrix or the cells the edit script passes through to stderr, for
+/// what remains after the common prefix and suffix, unless the matrix is too
+/// large to be filled.
+///
+/// The common prefix and suffix of the inputs are matched before the rest is
+/// aligned. What remains of large inputs is aligned with Hirschberg's
+/// divide-and-conquer algorithm, which needs O(min(n, m)) memory instead of
+/// the full n×m matrix. Either way the edit distance is the same as with the
+/// full matrix, but ties may be broken differently.
+pub fn lev<T: Eq>(s1: &[T], s2: &[T]) -> Vec<Action> {
+    lev_weighted(s1, s2, &Costs::default())
+}
+
+/// Like [`lev`], but computes the edit script of least total cost under
+/// `costs` rather than the one with the fewest changes.
+pub fn lev_weighted<T: Eq>(s1: &[T], s2: &[T], costs: &Costs) -> Vec<Action> {
+    let mut actions = vec![];
+    lev_with(s1, s2, costs, &mut actions);
+    actions
+}
+
+/// Like [`lev_weighted`], but passes the edit script to `hook` instead.
+pub(crate) fn lev_with<T: Eq, H: DiffHook + ?Sized>(
+    s1: &[T],
+    s2: &[T],
+    costs: &Costs,
+    hook: &mut H,
+) {
+    trim::trimmed(s1, s2, hook, |s1, s2, hook| lev_matrix(s1, s2, costs, hook));
+}
+
+fn traces_matrix() -> bool {
+    trace::is_enabled(Trace::Matrix) || trace::is_enabled(Trace::Path)
+}
+
+/// Fills the cost matrix of `s1` and `s2`, or falls back to Hirschberg's
+/// algorithm when it would be too large, and passes the edit script to
+/// `hook`.
+fn lev_matrix<T: Eq, H: DiffHook + ?Sized>(s1: &[T], s2: &[T], costs: &Costs, hook: &mut H) {
+    let n1 = s1.len();
+    let n2 = s2.len();
+    if (n1 + 1).saturating_mul(n2 + 1) > MATRIX_LIMIT {
+        if traces_matrix() {
+            trace::line(&format!(
+                "matrix: {n1} old by {n2} new items, too large to trace"
+            ));
+        }
+        let pair = costs.substitute < costs.insert + costs.delete;
+        edit::replay(&hirschberg::edits(s1, s2, costs), pair, hook);
+        return;
+    }
+    let mut matrix = Matrix::new(n1, n2);
+    matrix.set(0, 0, Cell::new(0, Step::Ignore));
+
+    for n2 in 1..n2 + 1 {
+        matrix.set(0, n2, Cell::new(n2 * costs.insert, Step::Add));
+    }
+    for n1 in 1..n1 + 1 {
+        matrix.set(n1, 0, Cell::new(n1 * costs.delete, Step::Remove));
+    }
+    for n1 in 1..n1 + 1 {
+        for n2 in 1..n2 + 1 {
+            if s1[n1 - 1] == s2[n2 - 1] {
+                let cost = matrix.get(n1 - 1, n2 - 1).cost();
+                matrix.set(n1, n2, Cell::new(cost, Step::Ignore));
+                continue;
+            }
+            let cell = [
+                (costs.delete + matrix.get(n1 - 1, n2).cost(), Step::Remove),
+                (costs.insert + matrix.get(n1, n2 - 1).cost(), Step::Add),
+                (
+                    costs.substitute + matrix.get(n1 - 1, n2 - 1).cost(),
+                    Step::Substitute,
+                ),
+            ]
+            .into_iter()
+            .min_by_key(|item| item.0)
+            .map(|(cost, step)| Cell::new(cost, step))
+            .unwrap();
+            matrix.set(n1, n2, cell);
+        }
+    }
+    let actions = matrix.backtrack();
+    if trace::is_enabled(Trace::Matrix) {
+        matrix.trace();
+    }
+    if trace::is_enabled(Trace::Path) {
+        matrix.trace_path(&actions);
+    }
+    for action in actions {
+        hook::visit(hook, &action);
+    }
+    hook.finish();
+}
+
+/// Returns how similar `s1` and `s2` are, as one minus their edit distance
+/// over their combined length.
+///
+/// The ratio is `1.0` for identical inputs and `0.0` when only one of them is
+/// empty; inputs of the same length that share nothing score `0.5`, since
+/// every item is substituted. Only the cost is computed, in O(min(n, m))
+/// memory, without building the edit script.
+pub fn similarity<T: Eq>(s1: &[T], s2: &[T]) -> f64 {
+    let total = s1.len() + s2.len();
+    if total == 0 {
+        return 1.0;
+    }
+    1.0 - distance(s1, s2) as f64 / total as f64
+}
+
+/// Returns the Levenshtein distance between `s1` and `s2`: the number of
+/// insertions, deletions and substitutions of [`lev`]'s edit script.
+///
+/// Only two rows of the cost matrix are kept, for the shorter input, and no
+/// items are copied.
+pub fn distance<T: Eq>(s1: &[T], s2: &[T]) -> usize {
+    let (prefix, suffix) = trim::common(s1, s2);
+    let (s1, s2) = (
+        &s1[prefix..s1.len() - suffix],
+        &s2[prefix..s2.len() - suffix],
+    );
+    let (long, short) = if s1.len() >= s2.len() {
+        (s1, s2)
+    } else {
+        (s2, s1)
+    };
+    hirschberg::last_row(long, short, false, &Costs::default())[short.len()]
+}
+
+/// Returns the Levenshtein distance between `s1` and `s2` if it is at most
+/// `max`, or `None` otherwise.
+///
+/// This is much cheaper than [`distance`] when `max` is small, as it only
+/// looks at the part of the cost matrix near the diagonal and stops as soon
+/// as the bound is exceeded.
+pub fn distance_within<T: Eq>(s1: &[T], s2: &[T], max: usize) -> Option<usize> {
+    distance_within_weighted(s1, s2, max, &Costs::default())
+}
+
+/// Like [`distance_within`], but bounds the least total cost under `costs`
+/// of an edit script, which [`lev_weighted`] finds, rather than the number of
+/// changes.
+pub fn distance_within_weighted<T: Eq>(
+    s1: &[T],
+    s2: &[T],
+    max: usize,
+    costs: &Costs,
+) -> Option<usize> {
+    let (prefix, suffix) = trim::common(s1, s2);
+    let (s1, s2) = (
+        &s1[prefix..s1.len() - suffix],
+        &s2[prefix..s2.len() - suffix],
+    );
+    band::distance(s1, s2, max, costs)
+}
+
+/// Inverts an edit script, so that it turns the new sequence back into the
+/// old one: additions become removals and substitutions are flipped.
+pub fn reverse(actions: &[Action]) -> Vec<Action> {
+    actions
+        .iter()
+        .map(|action| match *action {
+            Action::Add(i, j) => Action::Remove(j, i),
+            Action::Remove(i, j) => Action::Add(j, i),
+            Action::Substitute(i, j) => Action::Substitute(j, i),
+            Action::Ignore(i, j) => Action::Ignore(j, i),
+        })
+        .collect()
+}
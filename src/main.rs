@@ -1,168 +1,2681 @@
-#![allow(clippy::needless_range_loop)]
-
-use std::{env, error::Error, fs, process};
-
-use ansi_term::Color;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref TRACE: bool = env::var("TRACE").map(|val| val == "1").unwrap_or(false);
-}
-
-#[derive(Clone, Debug)]
-enum Action {
-    Add(usize, String),
-    Remove(usize, String),
-    Substitute(usize, String, String),
-    Ignore(usize, String),
-}
-
-fn dump(actions: &[Vec<Option<(usize, Action)>>]) {
-    if *TRACE {
-        assert!(!actions.is_empty());
-        for row in actions.iter() {
-            for val in row.iter() {
-                if let Some((x, a)) = val {
-                    let action = match a {
-                        Action::Add(_, _) => "A",
-                        Action::Remove(_, _) => "R",
-                        Action::Substitute(_, _, _) => "S",
-                        Action::Ignore(_, _) => "I",
-                    };
-                    print!("{x:>3} ({action}) ");
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    env,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+    mem,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ExitCode, Stdio},
+    sync::{mpsc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+#[cfg(feature = "syntect")]
+use lev_diff::syntax;
+use lev_diff::{
+    archive::{Archive, Member},
+    dir::{self, Entry, Kind, Tree},
+    filter::Filter,
+    format::{
+        self, context, ed, html, ifdef, inline, json, markdown,
+        merge::Labels,
+        normal, pretty, side_by_side,
+        stat::{self, Stat},
+        structure::Notation,
+        unified, Markers, Theme,
+    },
+    git,
+    heading::Headings,
+    input::{self, Compression, LineEnding},
+    merge::{self, Region},
+    moved::Moved,
+    normalize::{self, Normalization},
+    notebook,
+    options::{self, DiffOptions},
+    patch::{Hunk, Patch},
+    reverse, stream,
+    structure::{self, Change},
+    tokenize,
+    trace::{self, Trace},
+    Action, Costs, Diff,
+};
+use memmap2::Mmap;
+use notify::{EventKind, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use regex::Regex;
+use tracing::{debug, info_span, Level};
+use tracing_subscriber::fmt::format::FmtSpan;
+
+mod config;
+mod error;
+mod tui;
+
+use error::Error;
+
+/// The number of lines of each input held in memory with `--stream`.
+const STREAM_WINDOW: usize = 8192;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Algorithm {
+    /// Minimal Levenshtein edit script
+    Levenshtein,
+    /// Myers' O(ND) greedy algorithm
+    Myers,
+    /// Lines unique to both files first, like `git diff --patience`
+    Patience,
+    /// The rarest lines first, like `git diff --histogram`
+    Histogram,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Colored listing of every line
+    Pretty,
+    /// Unified diff
+    Unified,
+    /// Old and new lines in two columns
+    SideBySide,
+    /// JSON edit script
+    Json,
+    /// JSON object per action, one per line
+    Ndjson,
+    /// Standalone HTML page with both files side by side
+    Html,
+    /// Unified diff in a Markdown code block, or a table with `-y`
+    Markdown,
+    /// Number of changed lines per file
+    Stat,
+    /// Script for the `ed` editor that turns the old file into the new one
+    Ed,
+    /// The default output of POSIX `diff`
+    Normal,
+    /// Context diff
+    Context,
+    /// Merged file with `#ifdef` around the changes
+    #[value(skip)]
+    Ifdef,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is unset
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Granularity {
+    /// Compare whole lines
+    Line,
+    /// Compare words, whitespace and punctuation
+    Word,
+    /// Compare single characters
+    Char,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FileType {
+    /// Compare lines of text
+    Text,
+    /// Compare the data of JSON documents
+    Json,
+    /// Compare the data of YAML documents
+    Yaml,
+    /// Compare the data of TOML documents
+    Toml,
+    /// Compare the rows and cells of CSV tables
+    Csv,
+    /// Compare the lines of XML documents formatted canonically
+    Xml,
+    /// Compare the element trees of XML documents
+    XmlTree,
+    /// Compare the cells of Jupyter notebooks
+    Notebook,
+    /// Compare the keys and values of `.env` files
+    Dotenv,
+    /// Compare the sections and keys of INI files
+    Ini,
+}
+
+impl FileType {
+    /// Returns whether files of this type are compared by their data rather
+    /// than by lines.
+    fn is_structured(self) -> bool {
+        !matches!(self, FileType::Text | FileType::Xml)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    /// Guess from the content of each file
+    Auto,
+    /// UTF-8
+    #[value(name = "utf-8")]
+    Utf8,
+    /// UTF-16, little-endian
+    #[value(name = "utf-16le")]
+    Utf16Le,
+    /// UTF-16, big-endian
+    #[value(name = "utf-16be")]
+    Utf16Be,
+    /// ISO 8859-1
+    Latin1,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Palette {
+    /// Green, red and yellow, for dark backgrounds
+    Default,
+    /// Blue and orange, for red-green color blindness
+    Deuteranopia,
+    /// Dark colors without yellow, for light backgrounds
+    Light,
+    /// No colors, only bold, dim and italic text
+    Mono,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum UnicodeForm {
+    /// Canonical composition
+    Nfc,
+    /// Canonical decomposition
+    Nfd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TraceKind {
+    /// The matrix, the path and the timing
+    All,
+    /// The final cost matrix of every alignment
+    Matrix,
+    /// The cells that the edit script of every alignment passes through
+    Path,
+    /// How long reading, diffing and writing every file takes
+    Timing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ConflictStyle {
+    /// Our and their lines
+    Merge,
+    /// Our, the base and their lines
+    Diff3,
+    /// Like diff3, with lines both sides agree on moved out of the conflict
+    Zdiff3,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Apply a saved diff to a file and print the result
+    Apply {
+        /// Unified diff or JSON edit script
+        patch: PathBuf,
+        /// File to patch, or `-` for stdin
+        file: PathBuf,
+        /// Choose which hunks to apply, one at a time
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Merge the changes two files make to a common base
+    Merge {
+        /// Common ancestor
+        base: PathBuf,
+        /// Our version
+        ours: PathBuf,
+        /// Their version
+        theirs: PathBuf,
+        /// How to present conflicts
+        #[arg(long, value_enum, default_value_t = ConflictStyle::Merge)]
+        conflict_style: ConflictStyle,
+        /// Resolve conflicts one at a time
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Print a completion script for a shell
+    Completions {
+        /// Shell to complete in
+        shell: Shell,
+    },
+}
+
+/// Simple difftool using levenshtein algorithm
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    args_override_self = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Original file, directory or archive, `-` for stdin, or a URL
+    #[arg(required_unless_present_any = ["git_external_diff", "cmd", "string", "pairs"])]
+    file1: Option<PathBuf>,
+    /// Modified file, directory or archive, `-` for stdin, or a URL
+    #[arg(required_unless_present_any = ["git_external_diff", "rev", "cmd", "string", "clipboard", "pairs"])]
+    file2: Option<PathBuf>,
+    /// Diff FILE1 against its content at git revision REV
+    #[arg(long, value_name = "REV", conflicts_with = "file2")]
+    rev: Option<String>,
+    /// Accept the arguments git passes to GIT_EXTERNAL_DIFF
+    #[arg(
+        long,
+        num_args = 1..=7,
+        value_names = ["PATH", "OLD_FILE", "OLD_HEX", "OLD_MODE", "NEW_FILE", "NEW_HEX", "NEW_MODE"],
+        conflicts_with_all = ["file1", "file2"]
+    )]
+    git_external_diff: Option<Vec<PathBuf>>,
+    /// Diff the output of COMMAND, run by the shell, and when given again
+    /// against that of the second command
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        conflicts_with_all = ["file1", "file2", "rev", "git_external_diff", "watch", "tui", "stream"]
+    )]
+    cmd: Vec<String>,
+    /// Diff TEXT, and when given again against the second text, as lines
+    /// that end with a newline
+    #[arg(
+        long,
+        value_name = "TEXT",
+        conflicts_with_all = ["file1", "file2", "rev", "git_external_diff", "cmd", "watch", "tui", "stream"]
+    )]
+    string: Vec<String>,
+    /// Diff FILE1 against the text of the system clipboard
+    #[arg(
+        long,
+        conflicts_with_all = ["file2", "rev", "git_external_diff", "watch", "tui", "stream"]
+    )]
+    clipboard: bool,
+    /// Diff every pair of paths listed by MANIFEST, one tab-separated pair
+    /// per line, then write the exit status of each pair before its paths
+    #[arg(
+        long,
+        value_name = "MANIFEST",
+        conflicts_with_all = ["file1", "file2", "rev", "git_external_diff", "cmd", "string", "clipboard", "watch", "tui", "stream"]
+    )]
+    pairs: Option<PathBuf>,
+    /// Diff algorithm
+    #[arg(long, value_enum, default_value_t = Algorithm::Levenshtein)]
+    algorithm: Algorithm,
+    /// Cost of an inserted line for the Levenshtein algorithm
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    insert_cost: u32,
+    /// Cost of a deleted line for the Levenshtein algorithm
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    delete_cost: u32,
+    /// Cost of a substituted line for the Levenshtein algorithm
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    substitute_cost: u32,
+    /// Only pair changed lines that share at least PERCENT of their words
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    similarity_threshold: Option<u8>,
+    /// Unit of comparison
+    #[arg(long, value_enum, default_value_t = Granularity::Line)]
+    granularity: Granularity,
+    /// Match the rows of CSV tables by their cell in column NAME
+    #[arg(long, value_name = "NAME")]
+    key_column: Option<String>,
+    /// Hide the values in structural diffs, such as secrets in `.env` files
+    #[arg(long)]
+    mask_values: bool,
+    /// Also compare the outputs of notebook cells
+    #[arg(long)]
+    notebook_outputs: bool,
+    /// Also compare the execution counts of notebook cells
+    #[arg(long)]
+    notebook_execution_counts: bool,
+    /// How to compare the files, detected from their extensions by default
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    file_type: Option<FileType>,
+    /// Output the edit script that turns FILE2 into FILE1
+    #[arg(short = 'R', long)]
+    reverse: bool,
+    /// Diff again whenever one of the inputs changes
+    #[arg(long)]
+    watch: bool,
+    /// Browse the diff of two files in a full-screen viewer
+    #[arg(long, conflicts_with = "watch")]
+    tui: bool,
+    /// Diff two large files a window of lines at a time, as a unified or ndjson diff
+    #[arg(long, conflicts_with_all = ["watch", "tui", "brief", "rev"])]
+    stream: bool,
+    /// Don't diff files whose edit distance, the total cost of their changes,
+    /// is larger than K
+    #[arg(long, value_name = "K")]
+    max_distance: Option<usize>,
+    /// Diff at most N files at once when comparing directories
+    #[arg(short = 'j', long, value_name = "N")]
+    jobs: Option<usize>,
+    /// Only report whether the files differ
+    #[arg(short = 'q', long)]
+    brief: bool,
+    /// Treat a file that is missing on one side as empty
+    #[arg(short = 'N', long)]
+    new_file: bool,
+    /// Skip files and directories whose name matches PATTERN when comparing
+    /// directories, or only directories if it ends with `/`
+    #[arg(short = 'x', long, value_name = "PATTERN")]
+    exclude: Vec<String>,
+    /// Skip files and directories whose name matches a pattern of FILE, one
+    /// per line
+    #[arg(short = 'X', long, value_name = "FILE")]
+    exclude_from: Vec<PathBuf>,
+    /// Pair files that are only in one directory with files only in the
+    /// other that are at least PERCENT similar, and diff them as renames
+    #[arg(
+        short = 'M',
+        long,
+        value_name = "PERCENT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "50",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    find_renames: Option<u8>,
+    /// Follow symbolic links to the files and directories they point to,
+    /// which is the default
+    #[arg(long, overrides_with = "no_dereference")]
+    follow_symlinks: bool,
+    /// Compare the targets of symbolic links instead of following them
+    #[arg(long, overrides_with = "follow_symlinks")]
+    no_dereference: bool,
+    /// Never pipe the output through a pager
+    #[arg(long)]
+    no_pager: bool,
+    /// Ignore ~/.config/lev-diff/config.toml and .lev-diff.toml
+    #[arg(long)]
+    no_config: bool,
+    /// Write how the diff is computed to stderr: the cost matrix, the path
+    /// through it, or the time each phase takes
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all",
+        value_delimiter = ','
+    )]
+    trace: Vec<TraceKind>,
+    /// Log what is read, diffed and written with the time it takes to
+    /// stderr, and with more detail when repeated
+    #[arg(short = 'v', long, action = ArgAction::Count)]
+    verbose: u8,
+    /// Write the output to PATH instead of stdout, without colors unless
+    /// `--color=always` is given
+    #[arg(short = 'o', long, value_name = "PATH", conflicts_with_all = ["watch", "tui"])]
+    output: Option<PathBuf>,
+    /// Map input files into memory instead of reading them
+    #[arg(long)]
+    mmap: bool,
+    /// Encoding of the files, converted to UTF-8 before they are compared
+    #[arg(long, value_enum, default_value_t = Encoding::Auto)]
+    encoding: Encoding,
+    /// Compare the byte order marks of UTF-8 files instead of ignoring them
+    #[arg(long)]
+    strict_bom: bool,
+    /// Also ignore carriage returns at the end of lines that don't precede a
+    /// newline, as at the end of a file
+    #[arg(long)]
+    strip_trailing_cr: bool,
+    /// Expand tabs to spaces with a tab stop every N columns before
+    /// comparing and showing lines
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "8"
+    )]
+    expand_tabs: Option<usize>,
+    /// Highlight the syntax of lines as LANGUAGE, a name or a file
+    /// extension, instead of by the extension of the files
+    #[arg(long, value_name = "LANGUAGE")]
+    syntax: Option<String>,
+    /// Show LABEL instead of the name of the old file, and when given again
+    /// instead of the name of the new file
+    #[arg(long, value_name = "LABEL")]
+    label: Vec<String>,
+    /// Treat all files as text
+    #[arg(short = 'a', long)]
+    text: bool,
+    /// Ignore case differences when comparing
+    #[arg(short = 'i', long)]
+    ignore_case: bool,
+    /// Compare lines in this Unicode normalization form, so that text
+    /// written with composed and decomposed characters compares equal
+    #[arg(long, value_enum, value_name = "FORM")]
+    unicode_normalize: Option<UnicodeForm>,
+    /// Ignore changes whose lines are all blank
+    #[arg(short = 'B', long)]
+    ignore_blank_lines: bool,
+    /// Ignore changes whose lines all match REGEX
+    #[arg(short = 'I', long, value_name = "REGEX")]
+    ignore_matching_lines: Vec<Regex>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Pretty)]
+    format: Format,
+    /// When to color the output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = ColorChoice::Auto,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always"
+    )]
+    color: ColorChoice,
+    /// Color blocks of lines that were moved with the `moved` style
+    #[arg(long)]
+    color_moved: bool,
+    /// Built-in set of output colors
+    #[arg(long, value_enum, default_value_t = Palette::Default)]
+    palette: Palette,
+    /// Override output colors of the palette, e.g.
+    /// `add=blue,remove=#ff8800,context=dim`
+    #[arg(long, value_name = "THEME")]
+    theme: Option<String>,
+    /// Symbols that mark changes: `ascii`, `diff`, or overrides such as
+    /// `add=>,remove=<,substitute=|,arrow=|,to=->`
+    #[arg(long, value_name = "MARKERS")]
+    markers: Option<Markers>,
+    /// Output a unified diff with N lines of context
+    #[arg(
+        short = 'u',
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "3"
+    )]
+    unified: Option<usize>,
+    /// Output old and new lines in two columns
+    #[arg(short = 'y', long)]
+    side_by_side: bool,
+    /// Output the number of changed lines per file
+    #[arg(long)]
+    stat: bool,
+    /// Output a context diff
+    #[arg(short = 'c')]
+    context_diff: bool,
+    /// Show the function or section that each change is in after its hunk
+    /// header
+    #[arg(short = 'p', long, visible_alias = "show-c-function")]
+    show_function: bool,
+    /// Show the last line matching REGEX before each change after its hunk
+    /// header, instead of the function found by `-p`
+    #[arg(short = 'F', long, value_name = "REGEX")]
+    show_function_line: Vec<Regex>,
+    /// Output a merged file with the changes wrapped in `#ifdef NAME`
+    #[arg(short = 'D', long, value_name = "NAME")]
+    ifdef: Option<String>,
+    /// Output an ed script
+    #[arg(short = 'e', long)]
+    ed: bool,
+    /// Output at most N columns per line in side-by-side and stat mode
+    #[arg(short = 'W', long, value_name = "N", default_value_t = 130)]
+    width: usize,
+    /// Show only N unchanged lines around each change
+    #[arg(short = 'C', long, value_name = "N")]
+    context: Option<usize>,
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Returns whether `path` is an `http://` or `https://` URL that the input
+/// is fetched from.
+fn is_url(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Fetches the body of the response to a GET request for `url`.
+#[cfg(feature = "http")]
+fn fetch(url: &str) -> Result<Vec<u8>, Error> {
+    let fail = |err: ureq::Error| Error::Fetch {
+        url: url.to_string(),
+        reason: err.to_string(),
+    };
+    let mut response = ureq::get(url).call().map_err(fail)?;
+    response
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()
+        .map_err(fail)
+}
+
+#[cfg(not(feature = "http"))]
+fn fetch(url: &str) -> Result<Vec<u8>, Error> {
+    Err(Error::Fetch {
+        url: url.to_string(),
+        reason: "built without HTTP support".to_string(),
+    })
+}
+
+/// Reads the text of the system clipboard.
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> Result<Vec<u8>, Error> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|err| format!("clipboard: {err}"))?;
+    Ok(text.into_bytes())
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn read_clipboard() -> Result<Vec<u8>, Error> {
+    Err("clipboard: built without clipboard support".into())
+}
+
+/// Formats `time` in UTC the way `diff -u` dates files, or with `context`
+/// the way `diff -c` does.
+fn timestamp(time: SystemTime, context: bool) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let (days, secs) = (since_epoch.as_secs() / 86400, since_epoch.as_secs() % 86400);
+    let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+    // Converts days since 1970-01-01 to a date of the proleptic Gregorian
+    // calendar, counting in eras of 400 years that start on March 1.
+    let shifted = days + 719468;
+    let (era, day_of_era) = (shifted / 146097, shifted % 146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    if context {
+        const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+        const MONTHS: [&str; 12] = [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ];
+        let (weekday, month) = (WEEKDAYS[(days % 7) as usize], MONTHS[month as usize - 1]);
+        format!("{weekday} {month} {day:2} {hour:02}:{minute:02}:{second:02} {year}")
+    } else {
+        let nanos = since_epoch.subsec_nanos();
+        format!("{year}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}.{nanos:09} +0000")
+    }
+}
+
+/// Returns the name of a file for the header of a unified diff, or with
+/// `context` of a context diff, which like with GNU diff carries the
+/// modification time of the file if it has one.
+fn dated(name: &str, time: Option<SystemTime>, context: bool) -> String {
+    match time {
+        Some(time) => format!("{name}\t{}", timestamp(time, context)),
+        None => name.to_string(),
+    }
+}
+
+fn open_input(path: &Path) -> Result<Box<dyn BufRead>, Error> {
+    if is_stdin(path) {
+        Ok(Box::new(io::stdin().lock()))
+    } else if is_url(path) {
+        Ok(Box::new(io::Cursor::new(read_input(path)?)))
+    } else {
+        let file = fs::File::open(path).map_err(|err| Error::read(path, err))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+fn read_input(path: &Path) -> Result<Vec<u8>, Error> {
+    if is_stdin(path) {
+        let mut buf = vec![];
+        io::stdin()
+            .read_to_end(&mut buf)
+            .map_err(|err| Error::read(path, err))?;
+        Ok(buf)
+    } else if is_url(path) {
+        fetch(&path.to_string_lossy())
+    } else {
+        fs::read(path).map_err(|err| Error::read(path, err))
+    }
+}
+
+/// Reads the input at `path` as UTF-8 text.
+fn read_text(path: &Path) -> Result<String, Error> {
+    String::from_utf8(read_input(path)?).map_err(|err| Error::Encoding {
+        path: path.to_path_buf(),
+        encoding: input::Encoding::Utf8,
+        offset: err.utf8_error().valid_up_to(),
+    })
+}
+
+/// The bytes of an input, either read into memory or mapped from the file.
+enum Content {
+    Heap(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Content {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Content::Heap(bytes) => bytes,
+            Content::Mapped(map) => map,
+        }
+    }
+}
+
+/// Like [`read_input`], but maps files into memory instead of copying them.
+fn map_input(path: &Path) -> Result<Content, Error> {
+    if is_stdin(path) || is_url(path) {
+        return read_input(path).map(Content::Heap);
+    }
+    let file = fs::File::open(path).map_err(|err| Error::read(path, err))?;
+    // SAFETY: the map is only read while diffing. Like `diff` itself, we
+    // assume the inputs are not truncated while they are compared.
+    let map = unsafe { Mmap::map(&file).map_err(|err| Error::read(path, err))? };
+    Ok(Content::Mapped(map))
+}
+
+/// The outcome of a run, ordered by severity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Same,
+    Different,
+    Trouble,
+}
+
+impl Status {
+    /// Returns the exit status of a run with this outcome, as with `diff`.
+    fn code(self) -> u8 {
+        match self {
+            Status::Same => 0,
+            Status::Different => 1,
+            Status::Trouble => 2,
+        }
+    }
+}
+
+/// A pager reading the output through a pipe, waited on when dropped.
+struct Pager {
+    child: Child,
+    stdin: Option<io::BufWriter<ChildStdin>>,
+}
+
+impl Pager {
+    /// Starts `$PAGER`, or `less` when it is unset, unless it is empty or
+    /// `cat`. Like git, `less` is told to quit when the output fits on one
+    /// screen and to pass colors through.
+    fn spawn() -> Option<Self> {
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        if pager.is_empty() || pager == "cat" {
+            return None;
+        }
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg(&pager).stdin(Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+        let mut child = command.spawn().ok()?;
+        let stdin = child.stdin.take().map(io::BufWriter::new);
+        Some(Pager { child, stdin })
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        if let Some(mut stdin) = self.stdin.take() {
+            let _ = stdin.flush();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// Where the diff is written to.
+enum Output {
+    Stdout(io::StdoutLock<'static>),
+    Pager(Pager),
+    File(io::BufWriter<fs::File>),
+}
+
+impl Output {
+    /// Writes to the file at `path` if there is one, and otherwise pages
+    /// the output when `paging` is set and stdout is a terminal.
+    fn new(paging: bool, path: Option<&Path>) -> Result<Self, Error> {
+        if let Some(path) = path {
+            let file = fs::File::create(path).map_err(|err| Error::create(path, err))?;
+            return Ok(Output::File(io::BufWriter::new(file)));
+        }
+        if paging && io::stdout().is_terminal() {
+            if let Some(pager) = Pager::spawn() {
+                return Ok(Output::Pager(pager));
+            }
+        }
+        Ok(Output::Stdout(io::stdout().lock()))
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(out) => out.write(buf),
+            Output::File(out) => out.write(buf),
+            // Quitting the pager early is not an error, so the rest of the
+            // output is discarded.
+            Output::Pager(pager) => match pager.stdin.as_mut().unwrap().write(buf) {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(buf.len()),
+                result => result,
+            },
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(out) => out.flush(),
+            Output::File(out) => out.flush(),
+            Output::Pager(pager) => match pager.stdin.as_mut().unwrap().flush() {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            },
+        }
+    }
+}
+
+/// Returns whether to color the output, which is never the case by default
+/// when it goes `to_file`.
+fn use_color(choice: ColorChoice, to_file: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto if to_file => false,
+        ColorChoice::Auto => {
+            let no_color = env::var_os("NO_COLOR").is_some_and(|val| !val.is_empty());
+            !no_color && io::stdout().is_terminal()
+        }
+    }
+}
+
+/// Settings shared by every file pair of a run.
+struct Options {
+    diff: DiffOptions,
+    granularity: Granularity,
+    file_type: Option<FileType>,
+    key_column: Option<String>,
+    notebook: notebook::Include,
+    mask_values: bool,
+    format: Format,
+    filter: Filter,
+    context: Option<usize>,
+    unified_context: usize,
+    width: usize,
+    text: bool,
+    theme: Theme,
+    reverse: bool,
+    brief: bool,
+    new_file: bool,
+    walk: dir::Walk,
+    /// How similar files must be to be paired as renames, if they are.
+    renames: Option<f64>,
+    mmap: bool,
+    /// The encoding of the inputs, or `None` to detect it for each.
+    encoding: Option<input::Encoding>,
+    strict_bom: bool,
+    strip_trailing_cr: bool,
+    /// The tab stop width that tabs are expanded to, if they are.
+    expand_tabs: Option<usize>,
+    /// The names shown for the old and the new file instead of their paths.
+    labels: (Option<String>, Option<String>),
+    /// How the headings shown after hunk headers are found, if they are.
+    headings: Option<Headings>,
+    /// The language that lines are highlighted as instead of the one their
+    /// file extension tells.
+    syntax: Option<String>,
+    color_moved: bool,
+    /// Whether Markdown output is a side-by-side table.
+    table: bool,
+    /// The macro that selects the new lines of the ifdef format.
+    ifdef: String,
+    /// The counts of every file diffed so far, for the stat format.
+    stats: Mutex<Vec<(String, Option<Stat>)>>,
+}
+
+impl Options {
+    fn new(cli: &Cli) -> Result<Self, Error> {
+        let format = if cli.unified.is_some() {
+            Format::Unified
+        } else if cli.side_by_side && cli.format != Format::Markdown {
+            Format::SideBySide
+        } else if cli.stat {
+            Format::Stat
+        } else if cli.ifdef.is_some() {
+            Format::Ifdef
+        } else if cli.context_diff {
+            Format::Context
+        } else if cli.ed {
+            Format::Ed
+        } else {
+            cli.format
+        };
+        if cli.granularity != Granularity::Line && !matches!(format, Format::Pretty | Format::Json)
+        {
+            return Err("sub-line granularity only supports the pretty and json formats".into());
+        }
+        let structured = cli
+            .file_type
+            .is_some_and(|file_type| file_type != FileType::Text);
+        if structured && (format != Format::Pretty || cli.granularity != Granularity::Line) {
+            return Err("structural diffs only support the pretty format".into());
+        }
+        if let Some(language) = &cli.syntax {
+            #[cfg(feature = "syntect")]
+            if !syntax::is_known(language) {
+                return Err(format!("unknown syntax: {language}").into());
+            }
+            #[cfg(not(feature = "syntect"))]
+            return Err(
+                format!("cannot highlight {language}: built without syntax highlighting").into(),
+            );
+        }
+        let palette = match cli.palette {
+            Palette::Default => format::Palette::Default,
+            Palette::Deuteranopia => format::Palette::Deuteranopia,
+            Palette::Light => format::Palette::Light,
+            Palette::Mono => format::Palette::Mono,
+        };
+        let theme = Theme::palette(palette)
+            .overridden(cli.theme.as_deref().unwrap_or_default())
+            .map_err(|err| format!("invalid theme: {err}"))?;
+        if cli.label.len() > 2 {
+            return Err("--label can only be given twice".into());
+        }
+        let mut patterns = cli.exclude.clone();
+        for path in &cli.exclude_from {
+            let text = read_text(path)?;
+            let lines = text.lines().filter(|line| !line.trim().is_empty());
+            patterns.extend(lines.map(str::to_string));
+        }
+        let exclude =
+            dir::Exclude::new(&patterns).map_err(|err| format!("invalid pattern: {err}"))?;
+        let algorithm = match cli.algorithm {
+            Algorithm::Levenshtein => options::Algorithm::Levenshtein,
+            Algorithm::Myers => options::Algorithm::Myers,
+            Algorithm::Patience => options::Algorithm::Patience,
+            Algorithm::Histogram => options::Algorithm::Histogram,
+        };
+        Ok(Options {
+            diff: DiffOptions::new()
+                .algorithm(algorithm)
+                .max_distance(cli.max_distance)
+                .similarity_threshold(
+                    cli.similarity_threshold
+                        .map(|percent| f64::from(percent) / 100.0),
+                )
+                .costs(Costs {
+                    insert: cli.insert_cost as usize,
+                    delete: cli.delete_cost as usize,
+                    substitute: cli.substitute_cost as usize,
+                })
+                .normalization(Normalization {
+                    ignore_case: cli.ignore_case,
+                    ignore_all_space: false,
+                    unicode: cli.unicode_normalize.map(|form| match form {
+                        UnicodeForm::Nfc => normalize::UnicodeForm::Nfc,
+                        UnicodeForm::Nfd => normalize::UnicodeForm::Nfd,
+                    }),
+                }),
+            granularity: cli.granularity,
+            file_type: cli.file_type,
+            key_column: cli.key_column.clone(),
+            notebook: notebook::Include {
+                outputs: cli.notebook_outputs,
+                execution_counts: cli.notebook_execution_counts,
+            },
+            mask_values: cli.mask_values,
+            format,
+            filter: Filter {
+                blank_lines: cli.ignore_blank_lines,
+                matching: cli.ignore_matching_lines.clone(),
+            },
+            context: cli.context,
+            unified_context: cli.unified.or(cli.context).unwrap_or(3),
+            width: cli.width,
+            text: cli.text,
+            theme: Theme {
+                markers: cli.markers.clone().unwrap_or_default(),
+                ..match use_color(cli.color, cli.output.is_some()) {
+                    true => theme,
+                    false => Theme::plain(),
+                }
+            },
+            reverse: cli.reverse,
+            brief: cli.brief,
+            new_file: cli.new_file,
+            walk: dir::Walk {
+                exclude,
+                no_dereference: cli.no_dereference,
+            },
+            renames: cli.find_renames.map(|percent| f64::from(percent) / 100.0),
+            mmap: cli.mmap,
+            encoding: match cli.encoding {
+                Encoding::Auto => None,
+                Encoding::Utf8 => Some(input::Encoding::Utf8),
+                Encoding::Utf16Le => Some(input::Encoding::Utf16Le),
+                Encoding::Utf16Be => Some(input::Encoding::Utf16Be),
+                Encoding::Latin1 => Some(input::Encoding::Latin1),
+            },
+            strict_bom: cli.strict_bom,
+            strip_trailing_cr: cli.strip_trailing_cr,
+            expand_tabs: cli.expand_tabs,
+            labels: (cli.label.first().cloned(), cli.label.get(1).cloned()),
+            syntax: cli.syntax.clone(),
+            headings: match cli.show_function_line.is_empty() {
+                true => cli.show_function.then(Headings::default),
+                false => Some(Headings::Matching(cli.show_function_line.clone())),
+            },
+            color_moved: cli.color_moved,
+            table: cli.side_by_side,
+            ifdef: cli.ifdef.clone().unwrap_or_default(),
+            stats: Mutex::default(),
+        })
+    }
+
+    /// Returns the same settings with no counts gathered yet, for diffing a
+    /// file pair on another thread.
+    fn fork(&self) -> Self {
+        Options {
+            diff: self.diff.clone(),
+            granularity: self.granularity,
+            file_type: self.file_type,
+            key_column: self.key_column.clone(),
+            notebook: self.notebook,
+            mask_values: self.mask_values,
+            format: self.format,
+            filter: self.filter.clone(),
+            context: self.context,
+            unified_context: self.unified_context,
+            width: self.width,
+            text: self.text,
+            theme: self.theme.clone(),
+            reverse: self.reverse,
+            brief: self.brief,
+            new_file: self.new_file,
+            walk: self.walk.clone(),
+            renames: self.renames,
+            mmap: self.mmap,
+            encoding: self.encoding,
+            strict_bom: self.strict_bom,
+            strip_trailing_cr: self.strip_trailing_cr,
+            expand_tabs: self.expand_tabs,
+            labels: self.labels.clone(),
+            headings: self.headings.clone(),
+            syntax: self.syntax.clone(),
+            color_moved: self.color_moved,
+            table: self.table,
+            ifdef: self.ifdef.clone(),
+            stats: Mutex::default(),
+        }
+    }
+
+    /// Writes the counts gathered for the stat format, if any, and forgets
+    /// them.
+    fn write_stats<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let stats = mem::take(&mut *self.stats.lock().unwrap());
+        stat::write(out, &stats, self.width, &self.theme)
+    }
+
+    /// Returns how to compare a file pair, which is as structured documents
+    /// when no type was given and both names are those of one.
+    fn file_type(&self, old: &Input, new: &Input) -> FileType {
+        if let Some(file_type) = self.file_type {
+            return file_type;
+        }
+        let detect = |input: &Input| {
+            let path = Compression::strip(input.path());
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name == ".env" || name.starts_with(".env.") {
+                return FileType::Dotenv;
+            }
+            let extension = path.extension().unwrap_or_default();
+            match extension.to_string_lossy().to_ascii_lowercase().as_str() {
+                "json" => FileType::Json,
+                "yaml" | "yml" => FileType::Yaml,
+                "toml" => FileType::Toml,
+                "csv" => FileType::Csv,
+                "xml" => FileType::Xml,
+                "ipynb" => FileType::Notebook,
+                "env" => FileType::Dotenv,
+                "ini" | "cfg" => FileType::Ini,
+                _ => FileType::Text,
+            }
+        };
+        let file_type = match (detect(old), detect(new)) {
+            (type1, type2) if type1 == type2 => type1,
+            _ => FileType::Text,
+        };
+        // Structural changes are only listed by the pretty format.
+        let lines = self.format != Format::Pretty || self.granularity != Granularity::Line;
+        match file_type.is_structured() && lines {
+            true => FileType::Text,
+            false => file_type,
+        }
+    }
+
+    /// Reads the content of an input, converted to UTF-8 and without a byte
+    /// order mark unless those are compared.
+    fn read(&self, input: &Input) -> Result<Content, Error> {
+        let content = input.read(self.mmap)?;
+        // A given encoding is trusted to fit, unlike a detected one whose
+        // invalid bytes are escaped.
+        if let Some(encoding) = self.encoding {
+            if let Some(offset) = encoding.invalid_offset(&content) {
+                let path = input.path().to_path_buf();
+                return Err(Error::Encoding {
+                    path,
+                    encoding,
+                    offset,
+                });
+            }
+        }
+        let encoding = self
+            .encoding
+            .unwrap_or_else(|| input::Encoding::detect(&content));
+        let content = match encoding.to_utf8(&content) {
+            Cow::Borrowed(_) => content,
+            Cow::Owned(bytes) => Content::Heap(bytes),
+        };
+        if self.strict_bom || !content.starts_with(input::UTF8_BOM) {
+            return Ok(content);
+        }
+        let bom = input::UTF8_BOM.len();
+        match content {
+            Content::Heap(mut bytes) => {
+                bytes.drain(..bom);
+                Ok(Content::Heap(bytes))
+            }
+            Content::Mapped(map) => Ok(Content::Heap(map[bom..].to_vec())),
+        }
+    }
+
+    /// Returns the input for the file at `path`, or for the symbolic link
+    /// itself with `--no-dereference`.
+    fn input<'a>(&self, path: &'a Path) -> Input<'a> {
+        match self.walk.no_dereference && path.is_symlink() {
+            true => Input::link(path),
+            false => Input::new(path),
+        }
+    }
+
+    /// Returns the labeled inputs for `path1` and `path2`. With
+    /// `--new-file`, one that doesn't exist is read as empty, as long as the
+    /// other does.
+    fn inputs<'a>(&'a self, path1: &'a Path, path2: &'a Path) -> (Input<'a>, Input<'a>) {
+        let missing =
+            |path: &Path| self.new_file && !is_stdin(path) && !is_url(path) && !path.exists();
+        let (old, new) = match (missing(path1), missing(path2)) {
+            (true, false) => (Input::missing(path1), self.input(path2)),
+            (false, true) => (self.input(path1), Input::missing(path2)),
+            _ => (self.input(path1), self.input(path2)),
+        };
+        (old.labeled(&self.labels.0), new.labeled(&self.labels.1))
+    }
+
+    /// Returns whether only inputs with identical items compare equal.
+    fn compares_bytes(&self) -> bool {
+        let Normalization {
+            ignore_case,
+            ignore_all_space,
+            unicode,
+        } = *self.diff.get_normalization();
+        !ignore_case
+            && !ignore_all_space
+            && unicode.is_none()
+            && self.expand_tabs.is_none()
+            && !self.filter.blank_lines
+            && self.filter.matching.is_empty()
+    }
+}
+
+/// Where the content of an input comes from.
+enum Source<'a> {
+    /// A file, or stdin for `-`.
+    Path(&'a Path),
+    /// Content that was already loaded.
+    Bytes(Vec<u8>),
+    /// A file that doesn't exist, read as empty with `--new-file`.
+    Missing(&'a Path),
+    /// A symbolic link that is not followed with `--no-dereference`, read as
+    /// the line of its target.
+    Link(&'a Path),
+}
+
+/// Something to diff along with the name it is shown under.
+struct Input<'a> {
+    source: Source<'a>,
+    label: Cow<'a, str>,
+}
+
+impl<'a> Input<'a> {
+    fn new(path: &'a Path) -> Self {
+        Input {
+            source: Source::Path(path),
+            label: path.to_string_lossy(),
+        }
+    }
+
+    /// Returns the input for the symbolic link at `path` itself.
+    fn link(path: &'a Path) -> Self {
+        Input {
+            source: Source::Link(path),
+            label: path.to_string_lossy(),
+        }
+    }
+
+    /// Returns the input for the file at `path`, which doesn't exist.
+    fn missing(path: &'a Path) -> Self {
+        Input {
+            source: Source::Missing(path),
+            label: path.to_string_lossy(),
+        }
+    }
+
+    /// Returns the path that tells the type of the input: that of its file,
+    /// since a label that `--label` replaced it with may not say anything
+    /// about the type, or else its name.
+    fn path(&self) -> &Path {
+        match self.source {
+            Source::Path(path) | Source::Missing(path) | Source::Link(path) => path,
+            Source::Bytes(_) => Path::new(&*self.label),
+        }
+    }
+
+    /// Returns when the file was last modified, or the current time for
+    /// stdin and the epoch for a missing file. Inputs that aren't files or
+    /// are shown under another name have no time.
+    fn modified(&self) -> Option<SystemTime> {
+        let (Source::Path(path) | Source::Missing(path) | Source::Link(path)) = self.source else {
+            return None;
+        };
+        if self.label != path.to_string_lossy() {
+            return None;
+        }
+        match self.source {
+            Source::Missing(_) => Some(SystemTime::UNIX_EPOCH),
+            Source::Link(_) => fs::symlink_metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            _ if is_stdin(path) => Some(SystemTime::now()),
+            _ => fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        }
+    }
+
+    /// Shows the input under `label` instead, if there is one.
+    fn labeled(mut self, label: &'a Option<String>) -> Self {
+        if let Some(label) = label {
+            self.label = Cow::Borrowed(label);
+        }
+        self
+    }
+
+    /// Reads the content of the input, mapping files into memory when
+    /// `mmap` is set and decompressing them when their extension is that
+    /// of a compression format.
+    fn read(&self, mmap: bool) -> Result<Content, Error> {
+        match &self.source {
+            Source::Path(path) if !is_stdin(path) && Compression::detect(path).is_some() => {
+                let compression = Compression::detect(path).expect("checked by the guard");
+                let bytes = read_input(path)?;
+                let bytes = compression
+                    .decompress(&bytes)
+                    .map_err(|err| Error::read(path, err))?;
+                Ok(Content::Heap(bytes))
+            }
+            Source::Link(path) => {
+                let target = fs::read_link(path).map_err(|err| Error::read(path, err))?;
+                let mut bytes = target.into_os_string().into_encoded_bytes();
+                bytes.push(b'\n');
+                Ok(Content::Heap(bytes))
+            }
+            Source::Path(path) if mmap => map_input(path),
+            Source::Path(path) => read_input(path).map(Content::Heap),
+            Source::Bytes(bytes) => Ok(Content::Heap(bytes.clone())),
+            Source::Missing(_) => Ok(Content::Heap(vec![])),
+        }
+    }
+}
+
+/// Diffs two files and writes the result, returning whether they differ.
+///
+/// With a `header`, nothing is written for identical files and the header
+/// line is written before the diff of differing ones.
+#[tracing::instrument(skip_all, fields(old = %old.label, new = %new.label))]
+fn diff_files<W: Write>(
+    opts: &Options,
+    old: &Input,
+    new: &Input,
+    header: Option<&str>,
+    out: &mut W,
+) -> Result<bool, Error> {
+    let start = Instant::now();
+    let read = info_span!("read").entered();
+    let bytes1 = opts.read(old)?;
+    let bytes2 = opts.read(new)?;
+    debug!(old_bytes = bytes1.len(), new_bytes = bytes2.len());
+    drop(read);
+    trace::elapsed("read", start);
+    let binary = !opts.text && (input::is_binary(&bytes1) || input::is_binary(&bytes2));
+    let file_type = opts.file_type(old, new);
+    if !binary && file_type == FileType::Notebook {
+        let (s1, s2) = input::decode(&bytes1, &bytes2);
+        let parse = |input: &Input, text| {
+            notebook::parse(text, opts.notebook).map_err(|err| format!("{}: {err}", input.label))
+        };
+        match parse(old, &s1).and_then(|cells1| Ok((cells1, parse(new, &s2)?))) {
+            Ok(cells) => return diff_notebooks(opts, (old, new), cells, header, out),
+            Err(err) => fall_back(&err),
+        }
+    } else if !binary && file_type.is_structured() {
+        let (s1, s2) = input::decode(&bytes1, &bytes2);
+        let parse = |input: &Input, text| {
+            parse_document(opts, file_type, text).map_err(|err| format!("{}: {err}", input.label))
+        };
+        match parse(old, &s1).and_then(|value1| Ok((value1, parse(new, &s2)?))) {
+            Ok(values) => {
+                let notation = match file_type {
+                    FileType::Toml => Notation::Dotted,
+                    FileType::Ini | FileType::Dotenv => Notation::Text,
+                    _ => Notation::Jq,
+                };
+                return diff_documents(opts, (old, new), values, notation, header, out);
+            }
+            Err(err) => fall_back(&err),
+        }
+    }
+    let canonical = file_type == FileType::Xml;
+    if opts.brief && (*bytes1 == *bytes2 || binary) {
+        let differs = *bytes1 != *bytes2;
+        if differs {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+        }
+        return Ok(differs);
+    }
+    if binary {
+        if *bytes1 == *bytes2 {
+            return Ok(false);
+        }
+        if opts.format == Format::Stat {
+            let name = stat_name(&old.label, &new.label);
+            opts.stats.lock().unwrap().push((name, None));
+            return Ok(true);
+        }
+        writeln!(out, "Binary files {} and {} differ", old.label, new.label)?;
+        return Ok(true);
+    }
+    let (mut s1, mut s2) = input::decode(&bytes1, &bytes2);
+    if canonical {
+        let format = |input: &Input, text| {
+            structure::canonical_xml(text).map_err(|err| format!("{}: {err}", input.label))
+        };
+        match format(old, &s1).and_then(|xml1| Ok((xml1, format(new, &s2)?))) {
+            Ok((xml1, xml2)) => (s1, s2) = (Cow::Owned(xml1), Cow::Owned(xml2)),
+            Err(err) => fall_back(&err),
+        }
+    }
+    if let Some(width) = opts.expand_tabs {
+        if let Cow::Owned(text) = input::expand_tabs(&s1, width) {
+            s1 = Cow::Owned(text);
+        }
+        if let Cow::Owned(text) = input::expand_tabs(&s2, width) {
+            s2 = Cow::Owned(text);
+        }
+    }
+    let (items1, items2) = match opts.granularity {
+        Granularity::Line => (
+            split_lines(&s1, opts.strip_trailing_cr),
+            split_lines(&s2, opts.strip_trailing_cr),
+        ),
+        Granularity::Word => (tokenize::words(&s1), tokenize::words(&s2)),
+        Granularity::Char => (tokenize::chars(&s1), tokenize::chars(&s2)),
+    };
+    // Inputs that differ in their bytes may still have the same items, such
+    // as lines that only end differently, which don't need a diff to tell.
+    if opts.brief && opts.compares_bytes() && !canonical {
+        let differs = items1 != items2
+            || opts.granularity == Granularity::Line
+                && ends_with_newline(&s1) != ends_with_newline(&s2);
+        if differs {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+        }
+        return Ok(differs);
+    }
+    let start = Instant::now();
+    let actions = info_span!("diff", old_items = items1.len(), new_items = items2.len())
+        .in_scope(|| opts.diff.try_diff(&items1, &items2));
+    debug!(actions = actions.as_ref().map(Vec::len));
+    trace::elapsed("diff", start);
+    let Some(mut actions) = actions else {
+        let reason = match opts.brief {
+            true => "differ",
+            false => "are too different",
+        };
+        writeln!(out, "Files {} and {} {reason}", old.label, new.label)?;
+        return Ok(true);
+    };
+    let mut newlines = (ends_with_newline(&s1), ends_with_newline(&s2));
+    if opts.granularity == Granularity::Line {
+        mark_final_newlines(&mut actions, (items1.len(), items2.len()), newlines);
+    }
+    let filter = &opts.filter;
+    let differs = filter
+        .significant(&Diff {
+            old: &items1,
+            new: &items2,
+            actions: &actions,
+        })
+        .contains(&true);
+    if opts.brief {
+        if differs {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+        }
+        return Ok(differs);
+    }
+    let (mut ending1, mut ending2) = (LineEnding::detect(&s1), LineEnding::detect(&s2));
+    let endings_differ = ending1.is_some() && ending2.is_some() && ending1 != ending2;
+    if header.is_some() && !differs && !endings_differ {
+        return Ok(false);
+    }
+    let (mut old_name, mut new_name) = (old.label.as_ref(), new.label.as_ref());
+    let (mut items1, mut items2) = (&items1[..], &items2[..]);
+    let (mut time1, mut time2) = (old.modified(), new.modified());
+    if opts.reverse {
+        actions = reverse(&actions);
+        mem::swap(&mut old_name, &mut new_name);
+        mem::swap(&mut items1, &mut items2);
+        mem::swap(&mut ending1, &mut ending2);
+        newlines = (newlines.1, newlines.0);
+        mem::swap(&mut time1, &mut time2);
+    }
+    let diff = &Diff {
+        old: items1,
+        new: items2,
+        actions: &actions,
+    };
+    if let Some(header) = header.filter(|_| {
+        !matches!(
+            opts.format,
+            Format::Json | Format::Ndjson | Format::Html | Format::Stat
+        )
+    }) {
+        writeln!(out, "{header}")?;
+    }
+    // Line endings are not compared, but a change of them is worth knowing
+    // about, in every format.
+    let endings = match (ending1, ending2) {
+        (Some(ending1), Some(ending2)) if endings_differ => Some((ending1, ending2)),
+        _ => None,
+    };
+    let note = endings.map(|(ending1, ending2)| {
+        format!("Line endings differ: {ending1} in {old_name}, {ending2} in {new_name}")
+    });
+    match (&note, opts.format) {
+        (None, _) | (_, Format::Json | Format::Ndjson | Format::Html) => {}
+        // The output of these is data rather than a report, which the note
+        // would corrupt.
+        (Some(note), Format::Ed | Format::Ifdef | Format::Stat) => eprintln!("lev-diff: {note}"),
+        (Some(note), _) => writeln!(out, "{}", opts.theme.context.paint(note))?,
+    }
+    let start = Instant::now();
+    let _render = info_span!("render", format = ?opts.format).entered();
+    match opts.format {
+        Format::Pretty if opts.granularity != Granularity::Line => {
+            inline::write(out, diff, &opts.theme)?
+        }
+        Format::Pretty => {
+            if newlines.0 != newlines.1 {
+                let name = if newlines.0 { new_name } else { old_name };
+                let note = format!("No newline at end of {name}");
+                writeln!(out, "{}", opts.theme.context.paint(note))?;
+            }
+            let moved = opts.color_moved.then(|| Moved::new(diff));
+            let (moved, headings) = (moved.as_ref(), opts.headings.as_ref());
+            let highlighted = highlight(opts, (old, new), diff);
+            pretty::write(
+                out,
+                diff,
+                opts.context,
+                filter,
+                moved,
+                headings,
+                highlighted.as_ref(),
+                &opts.theme,
+            )?
+        }
+        Format::Unified => {
+            let (old_name, new_name) =
+                (dated(old_name, time1, false), dated(new_name, time2, false));
+            let context = opts.unified_context;
+            let headings = opts.headings.as_ref();
+            unified::write(
+                out, &old_name, &new_name, diff, context, filter, newlines, headings,
+            )?
+        }
+        Format::SideBySide => side_by_side::write(out, diff, opts.width)?,
+        Format::Json => json::write(out, old_name, new_name, diff, endings)?,
+        Format::Ndjson => json::write_ndjson(out, old_name, new_name, diff, endings, (0, 0))?,
+        Format::Markdown if opts.table => markdown::write_table(out, old_name, new_name, diff)?,
+        Format::Markdown => {
+            let context = opts.unified_context;
+            markdown::write(out, old_name, new_name, diff, context, filter, newlines)?
+        }
+        Format::Html => {
+            let context = opts.unified_context;
+            html::write(
+                out,
+                old_name,
+                new_name,
+                diff,
+                context,
+                filter,
+                note.as_deref(),
+            )?
+        }
+        Format::Stat if differs => {
+            let name = stat_name(old_name, new_name);
+            let stat = Stat::new(diff, filter);
+            opts.stats.lock().unwrap().push((name, Some(stat)));
+        }
+        Format::Stat => {}
+        Format::Ed => ed::write(out, diff, filter)?,
+        Format::Normal => normal::write(out, diff, filter, newlines)?,
+        Format::Ifdef => ifdef::write(out, diff, &opts.ifdef)?,
+        Format::Context => {
+            let (old_name, new_name) = (dated(old_name, time1, true), dated(new_name, time2, true));
+            context::write(
+                out,
+                &old_name,
+                &new_name,
+                diff,
+                opts.unified_context,
+                filter,
+                newlines,
+                opts.headings.as_ref(),
+            )?
+        }
+    }
+    trace::elapsed("write", start);
+    Ok(differs)
+}
+
+/// Highlights the lines of `diff` as the language given with `--syntax`, or
+/// else as that of the extension of the new or the old input, when the
+/// output is colored.
+#[cfg(feature = "syntect")]
+fn highlight<'a>(
+    opts: &Options,
+    (old, new): (&Input, &Input),
+    diff: &Diff<&'a str>,
+) -> Option<pretty::Highlighted<'a>> {
+    if opts.theme.is_plain() {
+        return None;
+    }
+    let extension = |input: &Input| {
+        let path = Compression::strip(input.path());
+        Some(path.extension()?.to_string_lossy().into_owned())
+    };
+    let language = opts
+        .syntax
+        .clone()
+        .or_else(|| extension(new).filter(|extension| syntax::is_known(extension)))
+        .or_else(|| extension(old).filter(|extension| syntax::is_known(extension)))?;
+    Some(pretty::Highlighted {
+        old: syntax::highlight(&language, diff.old)?,
+        new: syntax::highlight(&language, diff.new)?,
+    })
+}
+
+#[cfg(not(feature = "syntect"))]
+fn highlight<'a>(
+    _: &Options,
+    _: (&Input, &Input),
+    _: &Diff<&'a str>,
+) -> Option<pretty::Highlighted<'a>> {
+    None
+}
+
+/// Returns whether `text` ends with a newline, as an empty text counts as
+/// doing.
+fn ends_with_newline(text: &str) -> bool {
+    text.is_empty() || text.ends_with('\n')
+}
+
+/// Turns every unchanged pair of lines where only one is a last line
+/// without a newline into a change, since the lines differ in that.
+fn mark_final_newlines(actions: &mut [Action], lens: (usize, usize), newlines: (bool, bool)) {
+    for action in actions {
+        if let Action::Ignore(i, j) = *action {
+            let old_missing = !newlines.0 && i + 1 == lens.0;
+            let new_missing = !newlines.1 && j + 1 == lens.1;
+            if old_missing != new_missing {
+                *action = Action::Substitute(i, j);
+            }
+        }
+    }
+}
+
+/// Splits `text` into lines without their line breaks, and with `strip_cr`
+/// also without any carriage returns at their end.
+fn split_lines(text: &str, strip_cr: bool) -> Vec<&str> {
+    match strip_cr {
+        true => text
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect(),
+        false => text.lines().collect(),
+    }
+}
+
+/// Warns that documents which don't parse are still compared, line by line.
+fn fall_back(err: &str) {
+    eprintln!("lev-diff: {}", err.trim_end());
+    eprintln!("lev-diff: comparing the lines of both files instead");
+}
+
+/// Parses a structured document of type `file_type`.
+fn parse_document(
+    opts: &Options,
+    file_type: FileType,
+    text: &str,
+) -> Result<serde_json::Value, Error> {
+    match file_type {
+        FileType::Json => Ok(serde_json::from_str(text)?),
+        FileType::Yaml => Ok(structure::from_yaml(text)?),
+        FileType::Toml => Ok(structure::from_toml(text)?),
+        FileType::Csv => Ok(structure::from_csv(text, opts.key_column.as_deref())?),
+        FileType::XmlTree => Ok(structure::from_xml(text)?),
+        FileType::Dotenv => Ok(structure::from_dotenv(text)?),
+        FileType::Ini => Ok(structure::from_ini(text)?),
+        FileType::Text | FileType::Xml => unreachable!("text is diffed line by line"),
+        FileType::Notebook => unreachable!("notebooks are diffed cell by cell"),
+    }
+}
+
+/// Diffs two structured documents by their data and writes the result,
+/// returning whether they differ.
+fn diff_documents<W: Write>(
+    opts: &Options,
+    (old, new): (&Input, &Input),
+    (mut value1, mut value2): (serde_json::Value, serde_json::Value),
+    notation: Notation,
+    header: Option<&str>,
+    out: &mut W,
+) -> Result<bool, Error> {
+    if opts.reverse {
+        mem::swap(&mut value1, &mut value2);
+    }
+    let mut changes = structure::diff(&value1, &value2);
+    if opts.mask_values {
+        changes.iter_mut().for_each(Change::mask);
+    }
+    let differs = !changes.is_empty();
+    if opts.brief {
+        if differs {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+        }
+        return Ok(differs);
+    }
+    if let Some(header) = header.filter(|_| differs) {
+        writeln!(out, "{header}")?;
+    }
+    format::structure::write(out, &changes, notation, &opts.theme)?;
+    Ok(differs)
+}
+
+/// Diffs the cells of two notebooks and writes the result, returning whether
+/// they differ.
+fn diff_notebooks<W: Write>(
+    opts: &Options,
+    (old, new): (&Input, &Input),
+    (mut cells1, mut cells2): (Vec<notebook::Cell>, Vec<notebook::Cell>),
+    header: Option<&str>,
+    out: &mut W,
+) -> Result<bool, Error> {
+    if opts.reverse {
+        mem::swap(&mut cells1, &mut cells2);
+    }
+    let mut buffer = vec![];
+    let format = format::notebook::write;
+    let differs = format(
+        &mut buffer,
+        &cells1,
+        &cells2,
+        &opts.diff,
+        opts.context,
+        &opts.filter,
+        &opts.theme,
+    )?;
+    if opts.brief {
+        if differs {
+            writeln!(out, "Files {} and {} differ", old.label, new.label)?;
+        }
+        return Ok(differs);
+    }
+    if let Some(header) = header.filter(|_| differs) {
+        writeln!(out, "{header}")?;
+    }
+    out.write_all(&buffer)?;
+    Ok(differs)
+}
+
+/// Returns the name a file pair is listed under in the stat format.
+fn stat_name(old_name: &str, new_name: &str) -> String {
+    match old_name == new_name {
+        true => old_name.to_string(),
+        false => format!("{old_name} => {new_name}"),
+    }
+}
+
+/// One side of a directory comparison: a directory, or an archive that is
+/// compared like one.
+enum Root<'a> {
+    Dir(&'a Path),
+    Archive(&'a Path, Archive),
+}
+
+impl<'a> Root<'a> {
+    /// Returns whether `path` is a directory or an archive.
+    fn is_root(path: &Path) -> bool {
+        path.is_dir() || Archive::is_archive(path) && path.is_file()
+    }
+
+    /// Opens the directory or the archive at `path`.
+    fn open(path: &'a Path) -> Result<Self, Error> {
+        if path.is_dir() {
+            return Ok(Root::Dir(path));
+        }
+        let archive = Archive::open(path).map_err(|err| Error::read(path, err))?;
+        Ok(Root::Archive(path, archive))
+    }
+
+    fn path(&self) -> &'a Path {
+        match *self {
+            Root::Dir(path) | Root::Archive(path, _) => path,
+        }
+    }
+
+    /// Returns the input for `rel`, shown as `path`.
+    fn input<'b>(&self, opts: &Options, rel: &Path, path: &'b Path) -> Input<'b> {
+        let archive = match self {
+            Root::Dir(_) => return opts.input(path),
+            Root::Archive(_, archive) => archive,
+        };
+        let bytes = match archive.member(rel) {
+            Some(Member::File(bytes)) => bytes.clone(),
+            Some(Member::Link(target)) => {
+                let mut bytes = target.as_os_str().as_encoded_bytes().to_vec();
+                bytes.push(b'\n');
+                bytes
+            }
+            Some(Member::Dir) | None => return Input::missing(path),
+        };
+        Input {
+            source: Source::Bytes(bytes),
+            label: path.to_string_lossy(),
+        }
+    }
+}
+
+impl Tree for Root<'_> {
+    fn names(&self, rel: &Path) -> io::Result<BTreeSet<OsString>> {
+        match self {
+            Root::Dir(path) => path.names(rel),
+            Root::Archive(_, archive) => archive.names(rel),
+        }
+    }
+
+    fn kind(&self, rel: &Path, no_dereference: bool) -> Kind {
+        match self {
+            Root::Dir(path) => path.kind(rel, no_dereference),
+            Root::Archive(_, archive) => archive.kind(rel, no_dereference),
+        }
+    }
+
+    fn canonical(&self, rel: &Path) -> PathBuf {
+        match self {
+            Root::Dir(path) => path.canonical(rel),
+            Root::Archive(_, archive) => archive.canonical(rel),
+        }
+    }
+}
+
+/// Diffs every file of two directory trees.
+///
+/// Errors about single files are reported and the walk continues. The file
+/// pairs are diffed in parallel, but the output is written in walk order.
+fn diff_dirs<W: Write>(
+    opts: &Options,
+    root1: &Root,
+    root2: &Root,
+    out: &mut W,
+) -> Result<Status, Error> {
+    let (dir1, dir2) = (root1.path(), root2.path());
+    let mut entries = opts.walk.compare(root1, root2)?;
+    if opts.new_file || opts.renames.is_some() {
+        entries = opts.walk.expand(root1, root2, entries)?;
+    }
+    if let Some(threshold) = opts.renames {
+        entries = find_renames(opts, (root1, root2), entries, threshold);
+    }
+    // With `--new-file`, a file on one side only is diffed against nothing.
+    let diffed = |entry: &Entry| match entry {
+        Entry::Both(_) | Entry::Renamed(..) => true,
+        Entry::OnlyOld(_) | Entry::OnlyNew(_) => opts.new_file,
+        Entry::Mismatch(_) | Entry::Loop(_) => false,
+    };
+    let diffs = entries
+        .par_iter()
+        .filter(|entry| diffed(entry))
+        .map(|entry| {
+            let (rel1, rel2) = match entry {
+                Entry::Both(path) | Entry::OnlyOld(path) | Entry::OnlyNew(path) => (path, path),
+                Entry::Renamed(old, new, _) => (old, new),
+                Entry::Mismatch(_) | Entry::Loop(_) => unreachable!("mismatches are not diffed"),
+            };
+            let (path1, path2) = (dir1.join(rel1), dir2.join(rel2));
+            let header = format!("diff {} {}", path1.display(), path2.display());
+            let (old, new) = match entry {
+                Entry::OnlyOld(_) => (root1.input(opts, rel1, &path1), Input::missing(&path2)),
+                Entry::OnlyNew(_) => (Input::missing(&path1), root2.input(opts, rel2, &path2)),
+                _ => (
+                    root1.input(opts, rel1, &path1),
+                    root2.input(opts, rel2, &path2),
+                ),
+            };
+            let opts = opts.fork();
+            let mut buf = vec![];
+            let result = diff_files(&opts, &old, &new, Some(&header), &mut buf);
+            let stats = opts.stats.into_inner().unwrap();
+            (result.map_err(|err| err.to_string()), buf, stats)
+        })
+        .collect::<Vec<_>>();
+    let mut diffs = diffs.into_iter();
+    let mut status = Status::Same;
+    for entry in entries {
+        let entry_status = match &entry {
+            Entry::OnlyOld(path) | Entry::OnlyNew(path) if !opts.new_file => {
+                let root = if matches!(entry, Entry::OnlyOld(_)) {
+                    dir1
                 } else {
-                    print!("{:>7} ", "-");
+                    dir2
+                };
+                let path = root.join(path);
+                let parent = path.parent().unwrap_or(root);
+                let name = path.file_name().unwrap_or_default();
+                writeln!(
+                    out,
+                    "Only in {}: {}",
+                    parent.display(),
+                    name.to_string_lossy()
+                )?;
+                Status::Different
+            }
+            Entry::Mismatch(path) => {
+                let kind = |root: &Root| match root.kind(path, opts.walk.no_dereference) {
+                    Kind::Link => "symbolic link",
+                    Kind::Dir => "directory",
+                    Kind::File => "regular file",
+                };
+                writeln!(
+                    out,
+                    "File {} is a {} while file {} is a {}",
+                    dir1.join(path).display(),
+                    kind(root1),
+                    dir2.join(path).display(),
+                    kind(root2)
+                )?;
+                Status::Different
+            }
+            Entry::Loop(path) => {
+                let (path1, path2) = (dir1.join(path), dir2.join(path));
+                // The loop is usually made by a link on the side it is on.
+                let path = match path1.is_symlink() || !path2.exists() {
+                    true => path1,
+                    false => path2,
+                };
+                eprintln!("lev-diff: {}: recursive directory loop", path.display());
+                Status::Trouble
+            }
+            Entry::Both(_) | Entry::OnlyOld(_) | Entry::OnlyNew(_) | Entry::Renamed(..) => {
+                if let Entry::Renamed(old, new, similarity) = &entry {
+                    writeln!(
+                        out,
+                        "renamed: {} -> {} ({}% similar)",
+                        dir1.join(old).display(),
+                        dir2.join(new).display(),
+                        (similarity * 100.0).floor()
+                    )?;
+                }
+                let (result, buf, stats) = diffs.next().expect("one diff per file pair");
+                out.write_all(&buf)?;
+                opts.stats.lock().unwrap().extend(stats);
+                match result {
+                    Ok(false) => Status::Same,
+                    Ok(true) => Status::Different,
+                    Err(err) => {
+                        eprintln!("lev-diff: {err}");
+                        Status::Trouble
+                    }
+                }
+            }
+        };
+        status = status.max(entry_status);
+    }
+    Ok(status)
+}
+
+/// Pairs the files of `entries` that are only in `dir1` with those only in
+/// `dir2` that are at least `threshold` similar, line by line for text and
+/// byte for byte for binary files.
+fn find_renames(
+    opts: &Options,
+    (root1, root2): (&Root, &Root),
+    entries: Vec<Entry>,
+    threshold: f64,
+) -> Vec<Entry> {
+    let (dir1, dir2) = (root1.path(), root2.path());
+    let mut contents = HashMap::new();
+    for entry in &entries {
+        let (root, rel) = match entry {
+            Entry::OnlyOld(path) => (root1, path),
+            Entry::OnlyNew(path) => (root2, path),
+            _ => continue,
+        };
+        let path = root.path().join(rel);
+        // Files that can't be read are never paired.
+        if let Ok(content) = opts.read(&root.input(opts, rel, &path)) {
+            contents.insert(path, content);
+        }
+    }
+    let similarity = |old: &Path, new: &Path| {
+        let (Some(bytes1), Some(bytes2)) =
+            (contents.get(&dir1.join(old)), contents.get(&dir2.join(new)))
+        else {
+            return 0.0;
+        };
+        if input::is_binary(bytes1) || input::is_binary(bytes2) {
+            return if **bytes1 == **bytes2 { 1.0 } else { 0.0 };
+        }
+        let (s1, s2) = input::decode(bytes1, bytes2);
+        let lines1 = split_lines(&s1, opts.strip_trailing_cr);
+        let lines2 = split_lines(&s2, opts.strip_trailing_cr);
+        lev_diff::similarity(&lines1, &lines2)
+    };
+    dir::find_renames(entries, threshold, similarity)
+}
+
+/// Returns the name under which `path` is looked up inside a directory.
+fn file_name(path: &Path) -> Result<&OsStr, Error> {
+    if is_stdin(path) {
+        return Err("cannot compare stdin to a directory".into());
+    }
+    path.file_name()
+        .ok_or_else(|| format!("{}: not a file name", path.display()).into())
+}
+
+/// Writes `question` to stderr and reads the answer from stdin, or `None`
+/// at the end of input.
+fn prompt(question: &str) -> io::Result<Option<String>> {
+    eprint!("{question} ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(answer.trim().to_string()))
+}
+
+/// Opens `text` in `$VISUAL` or `$EDITOR` and returns the edited text.
+fn edit(text: &str) -> Result<String, Error> {
+    let path = env::temp_dir().join(format!("lev-diff-edit-{}.txt", std::process::id()));
+    fs::write(&path, text)?;
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    if !status?.success() {
+        return Err("the editor exited with an error".into());
+    }
+    Ok(edited?)
+}
+
+const APPLY_HELP: &str = "\
+y - apply this hunk
+n - skip this hunk
+e - edit this hunk, then apply it
+a - apply this hunk and all later ones
+q - skip this hunk and all later ones";
+
+const EDIT_HUNK_HELP: &str = "\
+# Edit the hunk, then save and quit. Lines starting with '-' are
+# removed, lines starting with '+' are added and lines starting with
+# ' ' are kept. Lines starting with '#' are ignored.
+";
+
+/// Asks about every hunk of `patch` and returns the patch made of the
+/// accepted ones, as edited.
+fn select_hunks(patch: Patch) -> Result<Patch, Error> {
+    let total = patch.hunks.len();
+    let mut accepted = vec![];
+    let mut all = false;
+    for (i, hunk) in patch.hunks.into_iter().enumerate() {
+        if all {
+            accepted.push(hunk);
+            continue;
+        }
+        eprint!("{hunk}");
+        let question = format!(
+            "({}/{total}) Apply this hunk at line {} [y,n,e,a,q,?]?",
+            i + 1,
+            hunk.old_start
+        );
+        loop {
+            match prompt(&question)?.as_deref() {
+                Some("y") => accepted.push(hunk),
+                Some("n") => {}
+                Some("a") => {
+                    all = true;
+                    accepted.push(hunk);
+                }
+                Some("q") | None => return Ok(Patch { hunks: accepted }),
+                Some("e") => {
+                    let edited = edit(&format!("{EDIT_HUNK_HELP}{hunk}"))
+                        .and_then(|text| Ok(Hunk::parse(hunk.old_start, &text)?));
+                    match edited {
+                        Ok(edited) => accepted.push(edited),
+                        Err(err) => {
+                            eprintln!("lev-diff: {err}");
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    eprintln!("{APPLY_HELP}");
+                    continue;
                 }
             }
-            println!();
+            break;
         }
-        println!();
     }
+    Ok(Patch { hunks: accepted })
 }
 
-fn backtrack_actions(actions: &[Vec<Option<(usize, Action)>>]) -> Vec<&Action> {
+const MERGE_HELP: &str = "\
+o - take our lines
+t - take their lines
+b - take our lines, then theirs
+e - edit the conflict and take the result
+s - leave this conflict unresolved
+q - leave this conflict and all later ones unresolved";
+
+/// Asks how to resolve every conflict of `regions` and returns the regions
+/// with the answers applied.
+fn resolve_conflicts(
+    regions: Vec<Region<&str>>,
+    style: format::merge::ConflictStyle,
+    labels: Labels,
+) -> Result<Vec<Region<String>>, Error> {
+    let total = regions.iter().filter(|region| region.is_conflict()).count();
     let mut result = vec![];
-    let mut n1 = actions.len();
-    let mut n2 = actions.get(0).map(|arr| arr.len()).unwrap_or(0);
-    while n1 > 0 && n2 > 0 {
-        let action = &actions[n1 - 1][n2 - 1].as_ref().unwrap().1;
-        result.push(action);
-        match action {
-            Action::Add(_, _) => n2 -= 1,
-            Action::Remove(_, _) => n1 -= 1,
-            Action::Substitute(_, _, _) | Action::Ignore(_, _) => {
-                n1 -= 1;
-                n2 -= 1;
-            }
-        }
-    }
-    result
-}
-
-fn lev<T: Eq + ToString>(s1: &[T], s2: &[T]) -> Vec<Action> {
-    let n1 = s1.len();
-    let n2 = s2.len();
-    let mut actions = Vec::with_capacity(n1 + 1);
-    for _ in 0..n1 + 1 {
-        actions.push(vec![None; n2 + 1]);
-    }
-
-    actions[0][0] = Some((0, Action::Ignore(0, String::from(""))));
-
-    for n2 in 1..n2 + 1 {
-        let n1 = 0;
-        actions[n1][n2] = Some((n2, Action::Add(n2, s2[n2 - 1].to_string())));
-        dump(&actions);
-    }
-    for n1 in 1..n1 + 1 {
-        let n2 = 0;
-        actions[n1][n2] = Some((n1, Action::Remove(n1, s1[n1 - 1].to_string())));
-        dump(&actions);
-    }
-    for n1 in 1..n1 + 1 {
-        for n2 in 1..n2 + 1 {
-            if s1[n1 - 1] == s2[n2 - 1] {
-                let x = actions[n1 - 1][n2 - 1].as_ref().map(|tup| tup.0).unwrap();
-                actions[n1][n2] = Some((x, Action::Ignore(n2, s1[n1 - 1].to_string())));
-                dump(&actions);
-                continue;
-            }
-            actions[n1][n2] = vec![
-                (
-                    1 + actions[n1 - 1][n2].as_ref().map(|tup| tup.0).unwrap(),
-                    Action::Remove(n1, s1[n1 - 1].to_string()),
-                ),
-                (
-                    1 + actions[n1][n2 - 1].as_ref().map(|tup| tup.0).unwrap(),
-                    Action::Add(n2, s2[n2 - 1].to_string()),
-                ),
-                (
-                    1 + actions[n1 - 1][n2 - 1].as_ref().map(|tup| tup.0).unwrap(),
-                    Action::Substitute(n2, s1[n1 - 1].to_string(), s2[n2 - 1].to_string()),
-                ),
-            ]
-            .iter()
-            .min_by_key(|item| item.0)
-            .cloned();
-            dump(&actions);
-        }
-    }
-    backtrack_actions(&actions)
-        .into_iter()
-        .cloned()
-        .rev()
-        .skip(1)
-        .collect()
-}
-
-fn print_actions(actions: &[Action]) {
-    let width = f32::log10(actions.len() as f32) as usize + 1;
-    let yellow = Color::Yellow;
-    let red = Color::Red;
-    let green = Color::Green;
-    for action in actions {
-        match action {
-            Action::Add(row, line) => {
-                println!(
-                    "{row:>width$} {action}| {line}",
-                    action = green.paint("+"),
-                    line = green.paint(line),
-                );
-            }
-            Action::Remove(row, line) => {
-                println!(
-                    "{row:>width$} {action}| {line}",
-                    action = red.paint("-"),
-                    line = red.paint(line),
-                );
-            }
-            Action::Substitute(row, line1, line2) => {
-                println!(
-                    "{row:>width$} {action}| {line1} ⇆  {line2}",
-                    action = yellow.paint("~"),
-                    line1 = red.paint(line1),
-                    line2 = green.paint(line2),
-                );
-            }
-            Action::Ignore(row, line) => {
-                println!("{row:>width$}  | {line}");
-            }
-        }
-    }
-}
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = env::args().collect::<Vec<String>>();
-    if args.len() < 3 {
-        eprintln!("Not enough arguments");
-        process::exit(1);
-    }
-    let s1 = fs::read_to_string(&args[1])?;
-    let s2 = fs::read_to_string(&args[2])?;
-    let actions = lev(
-        &s1.lines().collect::<Vec<&str>>(),
-        &s2.lines().collect::<Vec<_>>(),
+    let mut current = 0;
+    let mut regions = regions.into_iter();
+    while let Some(region) = regions.next() {
+        let Region::Conflict { ours, theirs, .. } = &region else {
+            result.push(region.map(str::to_string));
+            continue;
+        };
+        current += 1;
+        let mut shown = vec![];
+        format::merge::write(&mut shown, std::slice::from_ref(&region), style, labels)?;
+        let shown = String::from_utf8_lossy(&shown);
+        eprint!("{shown}");
+        let question = format!("({current}/{total}) Resolve this conflict [o,t,b,e,s,q,?]?");
+        let owned = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect();
+        let resolved = loop {
+            match prompt(&question)?.as_deref() {
+                Some("o") => break Some(owned(ours)),
+                Some("t") => break Some(owned(theirs)),
+                Some("b") => break Some(owned(&[&ours[..], &theirs[..]].concat())),
+                Some("e") => match edit(&shown) {
+                    Ok(text) => break Some(text.lines().map(str::to_string).collect()),
+                    Err(err) => eprintln!("lev-diff: {err}"),
+                },
+                Some("s") => break None,
+                Some("q") | None => {
+                    result.push(region.map(str::to_string));
+                    result.extend(regions.map(|region| region.map(str::to_string)));
+                    return Ok(result);
+                }
+                _ => eprintln!("{MERGE_HELP}"),
+            }
+        };
+        match resolved {
+            Some(lines) => result.push(Region::Resolved(lines)),
+            None => result.push(region.map(str::to_string)),
+        }
+    }
+    Ok(result)
+}
+
+/// Applies `patch` to `file` and writes the patched content to stdout.
+fn apply(patch: &Path, file: &Path, interactive: bool) -> Result<Status, Error> {
+    if is_stdin(patch) && is_stdin(file) {
+        return Err("only one input can be read from stdin".into());
+    }
+    if interactive && (is_stdin(patch) || is_stdin(file)) {
+        return Err("stdin is needed for answers in interactive mode".into());
+    }
+    let patch = read_text(patch)?;
+    let patch = match patch.trim_start().starts_with('{') {
+        true => json::read(&patch)?,
+        false => Patch::parse_unified(&patch)?,
+    };
+    let patch = match interactive {
+        true => select_hunks(patch)?,
+        false => patch,
+    };
+    let content = read_text(file)?;
+    let lines = patch.apply(&content.lines().collect::<Vec<_>>())?;
+    let mut out = io::stdout().lock();
+    for (i, line) in lines.iter().enumerate() {
+        write!(out, "{line}")?;
+        if i + 1 < lines.len() || patch.ends_with_newline(ends_with_newline(&content)) {
+            writeln!(out)?;
+        }
+    }
+    out.flush()?;
+    Ok(Status::Same)
+}
+
+/// Merges `ours` and `theirs` against `base` and writes the result to
+/// stdout, returning [`Status::Different`] if there are conflicts.
+fn merge(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    style: ConflictStyle,
+    interactive: bool,
+) -> Result<Status, Error> {
+    let stdin = [base, ours, theirs]
+        .iter()
+        .filter(|path| is_stdin(path))
+        .count();
+    if stdin > 1 {
+        return Err("only one input can be read from stdin".into());
+    }
+    if interactive && stdin > 0 {
+        return Err("stdin is needed for answers in interactive mode".into());
+    }
+    let (base_text, ours_text, theirs_text) =
+        (read_text(base)?, read_text(ours)?, read_text(theirs)?);
+    let regions = merge::merge(
+        &base_text.lines().collect::<Vec<_>>(),
+        &ours_text.lines().collect::<Vec<_>>(),
+        &theirs_text.lines().collect::<Vec<_>>(),
+    );
+    let mut out = io::stdout().lock();
+    let style = match style {
+        ConflictStyle::Merge => format::merge::ConflictStyle::Merge,
+        ConflictStyle::Diff3 => format::merge::ConflictStyle::Diff3,
+        ConflictStyle::Zdiff3 => format::merge::ConflictStyle::Zdiff3,
+    };
+    let labels = Labels {
+        base: &base.to_string_lossy(),
+        ours: &ours.to_string_lossy(),
+        theirs: &theirs.to_string_lossy(),
+    };
+    let regions = match interactive {
+        true => resolve_conflicts(regions, style, labels)?,
+        false => regions
+            .into_iter()
+            .map(|region| region.map(str::to_string))
+            .collect(),
+    };
+    format::merge::write(&mut out, &regions, style, labels)?;
+    out.flush()?;
+    match regions.iter().any(Region::is_conflict) {
+        true => Ok(Status::Different),
+        false => Ok(Status::Same),
+    }
+}
+
+/// Diffs a file pair in git's external diff calling convention.
+///
+/// Git treats a non-zero exit status as a failure of the driver, so this
+/// always reports [`Status::Same`] when the diff could be produced.
+fn git_external_diff<W: Write>(
+    opts: &Options,
+    args: &[PathBuf],
+    out: &mut W,
+) -> Result<Status, Error> {
+    let (path, old, new) = match args {
+        [path] => {
+            writeln!(out, "* Unmerged path {}", path.display())?;
+            return Ok(Status::Same);
+        }
+        [path, old, _old_hex, _old_mode, new, _new_hex, _new_mode] => (path, old, new),
+        _ => return Err("--git-external-diff expects 1 or 7 arguments".into()),
+    };
+    let label = |prefix: &str, file: &Path| match file == Path::new("/dev/null") {
+        true => Cow::Borrowed("/dev/null"),
+        false => Cow::Owned(format!("{prefix}/{}", path.display())),
+    };
+    let old = Input {
+        source: Source::Path(old),
+        label: label("a", old),
+    };
+    let new = Input {
+        source: Source::Path(new),
+        label: label("b", new),
+    };
+    let header = format!("diff --git a/{0} b/{0}", path.display());
+    diff_files(opts, &old, &new, Some(&header), out)?;
+    opts.write_stats(out)?;
+    Ok(Status::Same)
+}
+
+/// Diffs two inputs that are not directories and writes the statistics.
+fn diff_loaded<W: Write>(
+    opts: &Options,
+    old: &Input,
+    new: &Input,
+    out: &mut W,
+) -> Result<Status, Error> {
+    let differs = diff_files(opts, old, new, None, out)?;
+    opts.write_stats(out)?;
+    match differs {
+        true => Ok(Status::Different),
+        false => Ok(Status::Same),
+    }
+}
+
+/// Runs `command` with the shell, `sh` or `cmd` on Windows, and returns
+/// what it writes to stdout. A command that fails is warned about, but its
+/// output is still diffed, as with `diff <(command)`.
+fn run_command(command: &str) -> Result<Vec<u8>, Error> {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let output = std::process::Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("{command}: {err}"))?;
+    if !output.status.success() {
+        eprintln!("lev-diff: {command}: {}", output.status);
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the bytes of a `--string`, ending with a newline like the lines
+/// of a file unless it is empty.
+fn text_bytes(text: &str) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+        bytes.push(b'\n');
+    }
+    bytes
+}
+
+/// Diffs two files or directories and writes the statistics.
+fn diff_paths<W: Write>(
+    opts: &Options,
+    path1: &Path,
+    path2: &Path,
+    out: &mut W,
+) -> Result<Status, Error> {
+    let status = diff_pair(opts, path1, path2, None, out)?;
+    opts.write_stats(out)?;
+    Ok(status)
+}
+
+/// Diffs two files or directories. With a `header`, nothing is written for
+/// identical files and the header is written before the diff of others.
+fn diff_pair<W: Write>(
+    opts: &Options,
+    path1: &Path,
+    path2: &Path,
+    header: Option<&str>,
+    out: &mut W,
+) -> Result<Status, Error> {
+    if Root::is_root(path1) && Root::is_root(path2) {
+        return diff_dirs(opts, &Root::open(path1)?, &Root::open(path2)?, out);
+    }
+    // Like GNU diff, compare a file to the file of the same name inside the
+    // directory.
+    let (mut path1, mut path2) = (path1.to_owned(), path2.to_owned());
+    if path1.is_dir() {
+        path1.push(file_name(&path2)?);
+    } else if path2.is_dir() {
+        path2.push(file_name(&path1)?);
+    }
+    let (old, new) = opts.inputs(&path1, &path2);
+    match diff_files(opts, &old, &new, header, out)? {
+        true => Ok(Status::Different),
+        false => Ok(Status::Same),
+    }
+}
+
+/// Reads the pairs of paths listed by the manifest at `path`, one pair of
+/// tab-separated paths per line. Blank lines and lines that start with `#`
+/// are skipped.
+fn read_pairs(path: &Path) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    let text = read_text(path)?;
+    let mut pairs = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split('\t').collect::<Vec<_>>()[..] {
+            [old, new] if !old.is_empty() && !new.is_empty() => {
+                pairs.push((PathBuf::from(old), PathBuf::from(new)));
+            }
+            _ => {
+                let message = format!("{}:{}: expected OLD<TAB>NEW", path.display(), i + 1);
+                return Err(message.into());
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Diffs every pair of paths listed by the manifest at `path`, see
+/// [`read_pairs`], and then writes the statistics of all the pairs and the
+/// exit status of each pair in front of its paths.
+///
+/// Like the files of directories, the pairs are diffed in parallel but
+/// written in the order of the manifest, and errors about single pairs are
+/// reported without stopping the others.
+fn diff_pairs<W: Write>(opts: &Options, path: &Path, out: &mut W) -> Result<Status, Error> {
+    let pairs = read_pairs(path)?;
+    let diffs = pairs
+        .par_iter()
+        .map(|(path1, path2)| {
+            let header = format!("diff {} {}", path1.display(), path2.display());
+            let opts = opts.fork();
+            let mut buf = vec![];
+            let result = diff_pair(&opts, path1, path2, Some(&header), &mut buf);
+            let stats = opts.stats.into_inner().unwrap();
+            (result.map_err(|err| err.to_string()), buf, stats)
+        })
+        .collect::<Vec<_>>();
+    let mut statuses = vec![];
+    for (result, buf, stats) in diffs {
+        out.write_all(&buf)?;
+        opts.stats.lock().unwrap().extend(stats);
+        statuses.push(result.unwrap_or_else(|err| {
+            eprintln!("lev-diff: {err}");
+            Status::Trouble
+        }));
+    }
+    opts.write_stats(out)?;
+    for ((path1, path2), status) in pairs.iter().zip(&statuses) {
+        let code = status.code();
+        writeln!(out, "{code}\t{}\t{}", path1.display(), path2.display())?;
+    }
+    Ok(statuses.into_iter().max().unwrap_or(Status::Same))
+}
+
+/// Diffs two files or directories again every time one of them changes,
+/// until interrupted.
+fn watch(opts: &Options, path1: &Path, path2: &Path) -> Result<Status, Error> {
+    if is_stdin(path1) || is_stdin(path2) {
+        return Err("cannot watch stdin".into());
+    }
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watched = vec![];
+    for path in [path1, path2] {
+        let path = fs::canonicalize(path)?;
+        // Editors often replace files instead of writing them in place, so
+        // the parent directory of a file is watched rather than the file.
+        let (dir, mode) = match path.is_dir() {
+            true => (path.as_path(), RecursiveMode::Recursive),
+            false => (path.parent().unwrap_or(&path), RecursiveMode::NonRecursive),
+        };
+        watcher.watch(dir, mode)?;
+        watched.push(path);
+    }
+    let relevant = |event: &notify::Event| {
+        !matches!(event.kind, EventKind::Access(_))
+            && event
+                .paths
+                .iter()
+                .any(|path| watched.iter().any(|watched| path.starts_with(watched)))
+    };
+    loop {
+        let mut out = io::stdout().lock();
+        write!(out, "\x1b[2J\x1b[H")?;
+        if let Err(err) = diff_paths(opts, path1, path2, &mut out) {
+            writeln!(out, "lev-diff: {err}")?;
+        }
+        out.flush()?;
+        drop(out);
+        while !relevant(&rx.recv().map_err(|_| "the file watcher stopped")??) {}
+        // Let a burst of events settle before diffing again.
+        while rx.recv_timeout(Duration::from_millis(100)).is_ok() {}
+    }
+}
+
+/// Diffs two files without reading them whole, see [`stream::diff_chunks`].
+fn stream(
+    opts: &Options,
+    path1: &Path,
+    path2: &Path,
+    out: &mut impl Write,
+) -> Result<Status, Error> {
+    if path1.is_dir() || path2.is_dir() {
+        return Err("streaming only compares two files".into());
+    }
+    if !matches!(opts.format, Format::Unified | Format::Ndjson)
+        || opts.granularity != Granularity::Line
+    {
+        return Err("streaming only supports unified and ndjson line diffs".into());
+    }
+    let (old, new) = (
+        Input::new(path1).labeled(&opts.labels.0),
+        Input::new(path2).labeled(&opts.labels.1),
+    );
+    let mut old = (path1, old.modified(), old.label);
+    let mut new = (path2, new.modified(), new.label);
+    if opts.reverse {
+        mem::swap(&mut old, &mut new);
+    }
+    let ((path1, time1, old_name), (path2, time2, new_name)) = (old, new);
+    let (old, new) = (open_input(path1)?, open_input(path2)?);
+    if opts.format == Format::Ndjson {
+        let mut differs = false;
+        stream::diff_chunks(old, new, STREAM_WINDOW, &opts.diff, |diff, offset| {
+            differs |= opts.filter.significant(diff).contains(&true);
+            json::write_ndjson(out, &old_name, &new_name, diff, None, offset)
+        })?;
+        return match differs {
+            true => Ok(Status::Different),
+            false => Ok(Status::Same),
+        };
+    }
+    let (old_name, new_name) = (
+        dated(&old_name, time1, false),
+        dated(&new_name, time2, false),
     );
-    print_actions(&actions);
-    Ok(())
+    let context = opts.unified_context;
+    let mut writer = unified::Writer::new(out, &old_name, &new_name, context, &opts.filter)
+        .headings(opts.headings.as_ref());
+    stream::diff_chunks(old, new, STREAM_WINDOW, &opts.diff, |diff, offset| {
+        writer.write(diff, offset)
+    })?;
+    match writer.finish()? {
+        true => Ok(Status::Different),
+        false => Ok(Status::Same),
+    }
+}
+
+/// Opens two files in the full-screen viewer.
+fn view(opts: &Options, path1: &Path, path2: &Path) -> Result<Status, Error> {
+    if path1.is_dir() || path2.is_dir() {
+        return Err("the viewer only compares two files".into());
+    }
+    if opts.granularity != Granularity::Line {
+        return Err("the viewer only supports line granularity".into());
+    }
+    if !io::stdout().is_terminal() {
+        return Err("the viewer needs a terminal".into());
+    }
+    let (old, new) = opts.inputs(path1, path2);
+    let (bytes1, bytes2) = (opts.read(&old)?, opts.read(&new)?);
+    let (mut s1, mut s2) = input::decode(&bytes1, &bytes2);
+    if let Some(width) = opts.expand_tabs {
+        if let Cow::Owned(text) = input::expand_tabs(&s1, width) {
+            s1 = Cow::Owned(text);
+        }
+        if let Cow::Owned(text) = input::expand_tabs(&s2, width) {
+            s2 = Cow::Owned(text);
+        }
+    }
+    let (mut lines1, mut lines2) = (
+        s1.lines().collect::<Vec<_>>(),
+        s2.lines().collect::<Vec<_>>(),
+    );
+    let (mut old_name, mut new_name) = (old.label.as_ref(), new.label.as_ref());
+    if opts.reverse {
+        mem::swap(&mut lines1, &mut lines2);
+        mem::swap(&mut old_name, &mut new_name);
+    }
+    let diff = |ignore_all_space| {
+        let normalization = Normalization {
+            ignore_all_space,
+            ..opts.diff.get_normalization().clone()
+        };
+        let options = opts.diff.clone().normalization(normalization);
+        options.diff(&lines1, &lines2)
+    };
+    let actions = diff(false);
+    let initial = Diff {
+        old: &lines1,
+        new: &lines2,
+        actions: &actions,
+    };
+    tui::run(old_name, new_name, &initial, &diff)?;
+    match opts.filter.significant(&initial).contains(&true) {
+        true => Ok(Status::Different),
+        false => Ok(Status::Same),
+    }
+}
+
+/// Logs to stderr at the level that `-v` was given for, with the time
+/// every span took when it closes.
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => return,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false)
+        .with_ansi(io::stderr().is_terminal())
+        .with_writer(io::stderr)
+        .init();
+}
+
+fn run(cli: Cli) -> Result<Status, Error> {
+    match &cli.command {
+        Some(Command::Apply {
+            patch,
+            file,
+            interactive,
+        }) => return apply(patch, file, *interactive),
+        Some(Command::Merge {
+            base,
+            ours,
+            theirs,
+            conflict_style,
+            interactive,
+        }) => return merge(base, ours, theirs, *conflict_style, *interactive),
+        Some(Command::Completions { shell }) => {
+            // Generating straight to stdout panics when it is closed early.
+            let mut script = vec![];
+            clap_complete::generate(*shell, &mut Cli::command(), "lev-diff", &mut script);
+            io::stdout().write_all(&script)?;
+            return Ok(Status::Same);
+        }
+        None => {}
+    }
+    init_logging(cli.verbose);
+    for kind in &cli.trace {
+        let traces: &[Trace] = match kind {
+            TraceKind::All => &[Trace::Matrix, Trace::Path, Trace::Timing],
+            TraceKind::Matrix => &[Trace::Matrix],
+            TraceKind::Path => &[Trace::Path],
+            TraceKind::Timing => &[Trace::Timing],
+        };
+        traces.iter().copied().for_each(trace::enable);
+    }
+    let opts = Options::new(&cli)?;
+    if let Some(jobs) = cli.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()?;
+    }
+    // The viewer and watching write to the terminal themselves, so they are
+    // started before the pager or the output file is.
+    if let (true, Some(path1), Some(path2)) = (cli.tui || cli.watch, &cli.file1, &cli.file2) {
+        if is_stdin(path1) && is_stdin(path2) {
+            return Err("only one input can be read from stdin".into());
+        }
+        return match cli.tui {
+            true => view(&opts, path1, path2),
+            false => watch(&opts, path1, path2),
+        };
+    }
+    let mut out = Output::new(!cli.no_pager && !cli.watch, cli.output.as_deref())?;
+    if let Some(args) = &cli.git_external_diff {
+        let status = git_external_diff(&opts, args, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    if let (Some(rev), Some(path)) = (&cli.rev, &cli.file1) {
+        let old = Input {
+            source: Source::Bytes(git::read_at_revision(path, rev)?),
+            label: Cow::Owned(format!("{rev}:{}", path.display())),
+        };
+        let status = diff_loaded(&opts, &old, &Input::new(path), &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    if let Some(manifest) = &cli.pairs {
+        let status = diff_pairs(&opts, manifest, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    if let (true, Some(path)) = (cli.clipboard, &cli.file1) {
+        let new = Input {
+            source: Source::Bytes(read_clipboard()?),
+            label: Cow::Borrowed("clipboard"),
+        };
+        let old = opts.input(path).labeled(&opts.labels.0);
+        let new = new.labeled(&opts.labels.1);
+        let status = diff_loaded(&opts, &old, &new, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    if !cli.cmd.is_empty() {
+        let [command1, command2] = &cli.cmd[..] else {
+            return Err("--cmd must be given twice".into());
+        };
+        let old = Input {
+            source: Source::Bytes(run_command(command1)?),
+            label: Cow::Borrowed(command1),
+        };
+        let new = Input {
+            source: Source::Bytes(run_command(command2)?),
+            label: Cow::Borrowed(command2),
+        };
+        let (old, new) = (old.labeled(&opts.labels.0), new.labeled(&opts.labels.1));
+        let status = diff_loaded(&opts, &old, &new, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    if !cli.string.is_empty() {
+        let [text1, text2] = &cli.string[..] else {
+            return Err("--string must be given twice".into());
+        };
+        let text = |text: &str, label| Input {
+            source: Source::Bytes(text_bytes(text)),
+            label: Cow::Borrowed(label),
+        };
+        let old = text(text1, "string 1").labeled(&opts.labels.0);
+        let new = text(text2, "string 2").labeled(&opts.labels.1);
+        let status = diff_loaded(&opts, &old, &new, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    let (Some(path1), Some(path2)) = (cli.file1, cli.file2) else {
+        unreachable!("clap enforces both paths without a subcommand");
+    };
+    if is_stdin(&path1) && is_stdin(&path2) {
+        return Err("only one input can be read from stdin".into());
+    }
+    if cli.stream {
+        let status = stream(&opts, &path1, &path2, &mut out)?;
+        out.flush()?;
+        return Ok(status);
+    }
+    let status = diff_paths(&opts, &path1, &path2, &mut out)?;
+    out.flush()?;
+    Ok(status)
+}
+
+/// Parses the command line, with the defaults of the configuration files
+/// before its own arguments.
+fn parse_cli() -> Result<Cli, Error> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    // Subcommands take none of the flags that could be configured.
+    if cli.no_config || cli.command.is_some() {
+        return Ok(cli);
+    }
+    let defaults = config::args(&Cli::command(), &matches)?;
+    if defaults.is_empty() {
+        return Ok(cli);
+    }
+    let mut args: Vec<_> = env::args_os().collect();
+    args.splice(1..1, defaults);
+    Ok(Cli::parse_from(args))
+}
+
+fn main() -> ExitCode {
+    let cli = match parse_cli() {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("lev-diff: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(cli) {
+        Ok(status) => ExitCode::from(status.code()),
+        Err(err) if err.is_broken_pipe() => ExitCode::from(2),
+        Err(err) => {
+            eprintln!("lev-diff: {err}");
+            ExitCode::from(2)
+        }
+    }
 }
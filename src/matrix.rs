@@ -0,0 +1,141 @@
+//! The cost matrix of the Levenshtein algorithm, stored compactly.
+
+use crate::{trace, Action};
+
+/// The last step of the cheapest edit script reaching a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Step {
+    Ignore,
+    Remove,
+    Add,
+    Substitute,
+}
+
+/// A cell of the matrix, with the cost in the high bits and the step in the
+/// low two bits, or [`Cell::EMPTY`] until it is filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Cell(u64);
+
+impl Cell {
+    const EMPTY: Cell = Cell(u64::MAX);
+
+    pub(crate) fn new(cost: usize, step: Step) -> Self {
+        Cell((cost as u64) << 2 | step as u64)
+    }
+
+    pub(crate) fn cost(self) -> usize {
+        (self.0 >> 2) as usize
+    }
+
+    fn step(self) -> Step {
+        match self.0 & 3 {
+            0 => Step::Ignore,
+            1 => Step::Remove,
+            2 => Step::Add,
+            _ => Step::Substitute,
+        }
+    }
+}
+
+impl Step {
+    /// The letter that stands for the step in the trace.
+    fn letter(self) -> char {
+        match self {
+            Step::Ignore => 'I',
+            Step::Remove => 'R',
+            Step::Add => 'A',
+            Step::Substitute => 'S',
+        }
+    }
+}
+
+/// The `(n1 + 1) × (n2 + 1)` matrix of two sequences of lengths `n1` and
+/// `n2`, in a single allocation, row by row.
+pub(crate) struct Matrix {
+    cells: Vec<Cell>,
+    width: usize,
+}
+
+impl Matrix {
+    pub(crate) fn new(n1: usize, n2: usize) -> Self {
+        Matrix {
+            cells: vec![Cell::EMPTY; (n1 + 1) * (n2 + 1)],
+            width: n2 + 1,
+        }
+    }
+
+    pub(crate) fn get(&self, n1: usize, n2: usize) -> Cell {
+        self.cells[n1 * self.width + n2]
+    }
+
+    pub(crate) fn set(&mut self, n1: usize, n2: usize, cell: Cell) {
+        self.cells[n1 * self.width + n2] = cell;
+    }
+
+    /// Writes the matrix to the trace, a row per old item and a column per
+    /// new item, with the cost and the last step of every cell.
+    pub(crate) fn trace(&self) {
+        let height = self.cells.len() / self.width;
+        trace::line(&format!(
+            "matrix: {} old by {} new items, every cell the cost and the \
+             last step: (I)gnore, (R)emove, (A)dd or (S)ubstitute",
+            height - 1,
+            self.width - 1,
+        ));
+        let columns: String = (0..self.width).map(|n2| format!("{n2:>7}")).collect();
+        trace::line(&format!("matrix: {:>5}{columns}", ""));
+        for (n1, row) in self.cells.chunks(self.width).enumerate() {
+            let cells: String = row
+                .iter()
+                .map(|&cell| match cell {
+                    Cell::EMPTY => format!("{:>7}", "-"),
+                    cell => format!("{:>5} {}", cell.cost(), cell.step().letter()),
+                })
+                .collect();
+            trace::line(&format!("matrix: {n1:>5}{cells}"));
+        }
+    }
+
+    /// Writes the cells that `actions`, the edit script backtracked from
+    /// the matrix, pass through to the trace.
+    pub(crate) fn trace_path(&self, actions: &[Action]) {
+        let cost = self.cells.last().map_or(0, |cell| cell.cost());
+        trace::line(&format!("path: {} steps, total cost {cost}", actions.len()));
+        for action in actions {
+            let (n1, n2, step) = match *action {
+                Action::Add(i, j) => (i, j + 1, Step::Add),
+                Action::Remove(i, j) => (i + 1, j, Step::Remove),
+                Action::Substitute(i, j) => (i + 1, j + 1, Step::Substitute),
+                Action::Ignore(i, j) => (i + 1, j + 1, Step::Ignore),
+            };
+            let cost = self.get(n1, n2).cost();
+            trace::line(&format!("path: {step:?} to ({n1}, {n2}), cost {cost}"));
+        }
+    }
+
+    /// Follows the steps back from the last cell and returns the edit script
+    /// they make up, in order.
+    pub(crate) fn backtrack(&self) -> Vec<Action> {
+        let mut result = vec![];
+        let (mut n1, mut n2) = (self.cells.len() / self.width - 1, self.width - 1);
+        while n1 > 0 || n2 > 0 {
+            let action = match self.get(n1, n2).step() {
+                Step::Ignore => Action::Ignore(n1 - 1, n2 - 1),
+                Step::Remove => Action::Remove(n1 - 1, n2),
+                Step::Add => Action::Add(n1, n2 - 1),
+                Step::Substitute => Action::Substitute(n1 - 1, n2 - 1),
+            };
+            match action {
+                Action::Add(..) => n2 -= 1,
+                Action::Remove(..) => n1 -= 1,
+                Action::Substitute(..) | Action::Ignore(..) => {
+                    n1 -= 1;
+                    n2 -= 1;
+                }
+            }
+            result.push(action);
+        }
+        result.reverse();
+        result
+    }
+}
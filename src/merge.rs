@@ -0,0 +1,107 @@
+//! Three-way merging of two edit scripts against a common base.
+
+use crate::{edit::Edit, myers};
+
+/// A region of a three-way merge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Region<T> {
+    /// Lines that all sides agree on, or that only one side changed.
+    Resolved(Vec<T>),
+    /// Lines that both sides changed in different ways.
+    Conflict {
+        /// The lines of the common base.
+        base: Vec<T>,
+        /// The lines of our side.
+        ours: Vec<T>,
+        /// The lines of their side.
+        theirs: Vec<T>,
+    },
+}
+
+impl<T> Region<T> {
+    /// Returns `true` if this region is a conflict.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Region::Conflict { .. })
+    }
+
+    /// Converts every line of the region with `f`.
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Region<U> {
+        match self {
+            Region::Resolved(lines) => Region::Resolved(lines.into_iter().map(f).collect()),
+            Region::Conflict { base, ours, theirs } => Region::Conflict {
+                base: base.into_iter().map(&mut f).collect(),
+                ours: ours.into_iter().map(&mut f).collect(),
+                theirs: theirs.into_iter().map(&mut f).collect(),
+            },
+        }
+    }
+}
+
+/// For every item of `base`, returns the index of the item it is matched
+/// with in `other`, if any.
+fn matches<T: Eq>(base: &[T], other: &[T]) -> Vec<Option<usize>> {
+    let mut result = vec![None; base.len()];
+    for edit in myers::edits(base, other) {
+        if let Edit::Equal(i, j) = edit {
+            result[i] = Some(j);
+        }
+    }
+    result
+}
+
+fn push<T: Eq + Clone>(result: &mut Vec<Region<T>>, base: &[T], ours: &[T], theirs: &[T]) {
+    let region = if ours == base || ours == theirs {
+        Region::Resolved(theirs.to_vec())
+    } else if theirs == base {
+        Region::Resolved(ours.to_vec())
+    } else {
+        Region::Conflict {
+            base: base.to_vec(),
+            ours: ours.to_vec(),
+            theirs: theirs.to_vec(),
+        }
+    };
+    match (result.last_mut(), region) {
+        (_, Region::Resolved(lines)) if lines.is_empty() => {}
+        (Some(Region::Resolved(last)), Region::Resolved(lines)) => last.extend(lines),
+        (_, region) => result.push(region),
+    }
+}
+
+/// Merges the changes that `ours` and `theirs` make to `base`.
+///
+/// Changes made by only one side, or identically by both, are taken over;
+/// overlapping changes that differ are returned as conflicts. This is the
+/// algorithm used by `diff3 -m`.
+pub fn merge<T: Eq + Clone>(base: &[T], ours: &[T], theirs: &[T]) -> Vec<Region<T>> {
+    let m1 = matches(base, ours);
+    let m2 = matches(base, theirs);
+    let mut result = vec![];
+    let (mut i, mut a, mut t) = (0, 0, 0);
+    loop {
+        let mut k = 0;
+        while i + k < base.len() && m1[i + k] == Some(a + k) && m2[i + k] == Some(t + k) {
+            k += 1;
+        }
+        if k > 0 {
+            push(
+                &mut result,
+                &base[i..i + k],
+                &ours[a..a + k],
+                &theirs[t..t + k],
+            );
+            i += k;
+            a += k;
+            t += k;
+            continue;
+        }
+        let next = (i..base.len()).find_map(|j| Some((j, m1[j]?, m2[j]?)));
+        let (j, aj, tj) = next.unwrap_or((base.len(), ours.len(), theirs.len()));
+        push(&mut result, &base[i..j], &ours[a..aj], &theirs[t..tj]);
+        if next.is_none() {
+            break;
+        }
+        (i, a, t) = (j, aj, tj);
+    }
+    result
+}
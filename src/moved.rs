@@ -0,0 +1,86 @@
+//! Finding blocks of lines that were moved rather than removed and added.
+
+use std::collections::HashMap;
+
+use crate::{Action, Diff};
+
+/// Blocks with fewer alphanumeric characters than this are not reported as
+/// moved, so that stray braces and blank lines don't count, as in git.
+const MIN_ALNUM: usize = 20;
+
+/// Which removed and added lines of a diff belong to a block that appears
+/// verbatim on the other side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Moved {
+    /// For every old line, whether it was removed here and added elsewhere.
+    pub old: Vec<bool>,
+    /// For every new line, whether it was added here and removed elsewhere.
+    pub new: Vec<bool>,
+}
+
+impl Moved {
+    /// Pairs up the added lines of `diff` with removed lines of the same text,
+    /// longest blocks first from the top of the new file.
+    pub fn new(diff: &Diff<&str>) -> Self {
+        let (n1, n2) = (diff.old.len(), diff.new.len());
+        let mut removed = vec![false; n1];
+        let mut added = vec![false; n2];
+        for action in diff.actions {
+            match *action {
+                Action::Add(_, j) => added[j] = true,
+                Action::Remove(i, _) => removed[i] = true,
+                Action::Substitute(i, j) => {
+                    removed[i] = true;
+                    added[j] = true;
+                }
+                Action::Ignore(..) => {}
+            }
+        }
+        let mut candidates = HashMap::<&str, Vec<usize>>::new();
+        for i in (0..n1).filter(|&i| removed[i]) {
+            candidates.entry(diff.old[i]).or_default().push(i);
+        }
+        let mut moved = Moved {
+            old: vec![false; n1],
+            new: vec![false; n2],
+        };
+        let mut j = 0;
+        while j < n2 {
+            if !added[j] {
+                j += 1;
+                continue;
+            }
+            let block_len = |i: usize| {
+                (0..)
+                    .take_while(|&k| {
+                        i + k < n1
+                            && j + k < n2
+                            && removed[i + k]
+                            && added[j + k]
+                            && !moved.old[i + k]
+                            && diff.old[i + k] == diff.new[j + k]
+                    })
+                    .count()
+            };
+            let (len, i) = candidates
+                .get(diff.new[j])
+                .into_iter()
+                .flatten()
+                .map(|&i| (block_len(i), i))
+                .max_by_key(|&(len, i)| (len, std::cmp::Reverse(i)))
+                .unwrap_or((0, 0));
+            let alnum: usize = diff.new[j..j + len]
+                .iter()
+                .map(|line| line.chars().filter(|c| c.is_alphanumeric()).count())
+                .sum();
+            if len > 0 && alnum >= MIN_ALNUM {
+                moved.old[i..i + len].fill(true);
+                moved.new[j..j + len].fill(true);
+                j += len;
+            } else {
+                j += 1;
+            }
+        }
+        moved
+    }
+}
@@ -0,0 +1,150 @@
+//! Myers' O(ND) greedy diff algorithm.
+
+use crate::{
+    edit::{self, Edit},
+    hook::DiffHook,
+    trim, Action,
+};
+
+/// The furthest reaching paths of one direction of [`middle_snake`]: the
+/// furthest x of each diagonal k, at index `k + offset`.
+struct Frontier {
+    v: Vec<isize>,
+    offset: isize,
+}
+
+impl Frontier {
+    fn new(max: usize) -> Self {
+        Frontier {
+            v: vec![0; 2 * max + 3],
+            offset: max as isize + 1,
+        }
+    }
+
+    fn get(&self, k: isize) -> isize {
+        self.v[(k + self.offset) as usize]
+    }
+
+    fn set(&mut self, k: isize, x: isize) {
+        self.v[(k + self.offset) as usize] = x;
+    }
+
+    /// Returns where the path of diagonal `k` starts at step `d`, from the
+    /// furthest reaching paths of step `d - 1`.
+    fn start(&self, k: isize, d: isize) -> isize {
+        match k == -d || (k != d && self.get(k - 1) < self.get(k + 1)) {
+            true => self.get(k + 1),
+            false => self.get(k - 1) + 1,
+        }
+    }
+}
+
+/// Returns a point that a shortest edit script of `s1` and `s2` passes
+/// through, with at least one edit on either side of it.
+///
+/// The inputs must differ in their first and their last items. Paths are
+/// extended from both ends at once until they overlap, which takes
+/// O((n + m) * d) time and O(n + m) memory.
+fn middle_snake<T: Eq>(
+    s1: &[T],
+    s2: &[T],
+    forward: &mut Frontier,
+    backward: &mut Frontier,
+) -> (usize, usize) {
+    let (n, m) = (s1.len() as isize, s2.len() as isize);
+    let delta = n - m;
+    forward.set(1, 0);
+    backward.set(1, 0);
+    for d in 0..=(n + m + 1) / 2 {
+        for k in (-d..=d).step_by(2) {
+            let mut x = forward.start(k, d);
+            let mut y = x - k;
+            while x < n && y < m && s1[x as usize] == s2[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward.set(k, x);
+            // The backward paths of step `d - 1` are on the diagonals of the
+            // other parity when `delta` is odd.
+            let reverse_k = delta - k;
+            if delta % 2 != 0 && (1 - d..d).contains(&reverse_k) && x + backward.get(reverse_k) >= n
+            {
+                return (x as usize, y as usize);
+            }
+        }
+        // The backward paths count x and y from the ends of the inputs.
+        for k in (-d..=d).step_by(2) {
+            let mut x = backward.start(k, d);
+            let mut y = x - k;
+            while x < n && y < m && s1[(n - x - 1) as usize] == s2[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward.set(k, x);
+            let forward_k = delta - k;
+            if delta % 2 == 0 && (-d..=d).contains(&forward_k) && forward.get(forward_k) + x >= n {
+                let x = forward.get(forward_k);
+                return (x as usize, (x - forward_k) as usize);
+            }
+        }
+    }
+    unreachable!("the paths of both directions meet by the middle step")
+}
+
+/// Appends the edits that turn `s1` into `s2` to `out`, with the indices
+/// moved by `o1` and `o2`.
+fn conquer<T: Eq>(
+    s1: &[T],
+    s2: &[T],
+    (o1, o2): (usize, usize),
+    frontiers: &mut (Frontier, Frontier),
+    out: &mut Vec<Edit>,
+) {
+    let (prefix, suffix) = trim::common(s1, s2);
+    out.extend((0..prefix).map(|i| Edit::Equal(o1 + i, o2 + i)));
+    let (n, m) = (s1.len() - suffix, s2.len() - suffix);
+    let (inner1, inner2) = (&s1[prefix..n], &s2[prefix..m]);
+    let (p1, p2) = (o1 + prefix, o2 + prefix);
+    if inner1.is_empty() {
+        out.extend((0..inner2.len()).map(|j| Edit::Insert(p2 + j)));
+    } else if inner2.is_empty() {
+        out.extend((0..inner1.len()).map(|i| Edit::Delete(p1 + i)));
+    } else {
+        let (forward, backward) = frontiers;
+        let (x, y) = middle_snake(inner1, inner2, forward, backward);
+        conquer(&inner1[..x], &inner2[..y], (p1, p2), frontiers, out);
+        conquer(&inner1[x..], &inner2[y..], (p1 + x, p2 + y), frontiers, out);
+    }
+    out.extend((0..suffix).map(|i| Edit::Equal(o1 + n + i, o2 + m + i)));
+}
+
+/// Returns a shortest edit script of `s1` and `s2`, found by splitting the
+/// inputs at the middle of one and diffing both halves in turn, so that
+/// only O(n + m) memory is needed.
+pub(crate) fn edits<T: Eq>(s1: &[T], s2: &[T]) -> Vec<Edit> {
+    let max = s1.len() + s2.len();
+    let mut frontiers = (Frontier::new(max), Frontier::new(max));
+    let mut result = vec![];
+    conquer(s1, s2, (0, 0), &mut frontiers, &mut result);
+    result
+}
+
+/// Computes the edit script that turns `s1` into `s2` with the Myers
+/// algorithm.
+///
+/// This runs in O((n + m) * d) time and O(n + m) memory, where `d` is the
+/// size of the minimal insert/delete script, so it is much faster than
+/// [`lev`](crate::lev) on large, mostly similar inputs. Neighbouring
+/// removals and additions are reported as [`Action::Substitute`].
+pub fn myers<T: Eq>(s1: &[T], s2: &[T]) -> Vec<Action> {
+    let mut actions = vec![];
+    myers_with(s1, s2, &mut actions);
+    actions
+}
+
+/// Like [`myers`], but passes the edit script to `hook` instead.
+pub(crate) fn myers_with<T: Eq, H: DiffHook + ?Sized>(s1: &[T], s2: &[T], hook: &mut H) {
+    trim::trimmed(s1, s2, hook, |s1, s2, hook| {
+        edit::replay(&edits(s1, s2), true, hook)
+    });
+}
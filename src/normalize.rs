@@ -0,0 +1,84 @@
+//! Comparing items by a normalized form while keeping the original text.
+
+use std::{borrow::Cow, fmt};
+
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
+
+/// A Unicode normalization form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnicodeForm {
+    /// Canonical composition, as most systems write text.
+    Nfc,
+    /// Canonical decomposition, as macOS stores file names.
+    Nfd,
+}
+
+/// Which differences to disregard when comparing items.
+#[derive(Clone, Debug, Default)]
+pub struct Normalization {
+    /// Compare with Unicode case folding.
+    pub ignore_case: bool,
+    /// Compare with all whitespace removed.
+    pub ignore_all_space: bool,
+    /// Compare in this Unicode normalization form, so that canonically
+    /// equivalent text compares equal.
+    pub unicode: Option<UnicodeForm>,
+}
+
+impl Normalization {
+    /// Returns the form of `text` that is used for comparison.
+    pub fn key<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(text);
+        match self.unicode {
+            Some(UnicodeForm::Nfc) if !is_nfc(&key) => key = Cow::Owned(key.nfc().collect()),
+            Some(UnicodeForm::Nfd) if !is_nfd(&key) => key = Cow::Owned(key.nfd().collect()),
+            _ => {}
+        }
+        if self.ignore_case {
+            key = Cow::Owned(caseless::default_case_fold_str(&key));
+        }
+        if self.ignore_all_space {
+            key = Cow::Owned(key.chars().filter(|c| !c.is_whitespace()).collect());
+        }
+        key
+    }
+
+    /// Wraps every item of `items` for comparison under these rules.
+    pub fn apply<'a>(&self, items: &[&'a str]) -> Vec<Normalized<'a>> {
+        items
+            .iter()
+            .map(|text| Normalized {
+                text,
+                key: self.key(text),
+            })
+            .collect()
+    }
+}
+
+/// An item that compares by its normalized key but displays as written.
+#[derive(Clone, Debug)]
+pub struct Normalized<'a> {
+    text: &'a str,
+    key: Cow<'a, str>,
+}
+
+impl Normalized<'_> {
+    /// Returns the original text.
+    pub fn text(&self) -> &str {
+        self.text
+    }
+}
+
+impl PartialEq for Normalized<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Normalized<'_> {}
+
+impl fmt::Display for Normalized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text)
+    }
+}
@@ -0,0 +1,119 @@
+//! Reading Jupyter notebooks as the cells that make them up.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Text that notebooks store either whole or as a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Text {
+    Whole(String),
+    Lines(Vec<String>),
+}
+
+impl Text {
+    fn lines(self) -> Vec<String> {
+        let text = match self {
+            Text::Whole(text) => text,
+            Text::Lines(lines) => lines.concat(),
+        };
+        text.lines().map(str::to_string).collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawCell {
+    cell_type: String,
+    source: Text,
+    #[serde(default)]
+    outputs: Vec<Value>,
+    #[serde(default)]
+    execution_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RawNotebook {
+    cells: Vec<RawCell>,
+}
+
+/// Which parts of a cell besides its source are compared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Include {
+    /// The outputs of code cells.
+    pub outputs: bool,
+    /// The execution counts of code cells.
+    pub execution_counts: bool,
+}
+
+/// A cell of a notebook.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Cell {
+    /// The type of the cell, such as `code` or `markdown`.
+    pub kind: String,
+    /// The lines of the source of the cell.
+    pub source: Vec<String>,
+    /// The lines the cell is compared by: its source, preceded by its
+    /// execution count and followed by its outputs when those are included.
+    pub lines: Vec<String>,
+}
+
+/// Returns the lines of an output of a code cell, with a line naming its
+/// type first.
+fn output_lines(output: &Value) -> Vec<String> {
+    let kind = output["output_type"].as_str().unwrap_or("output");
+    let mut lines = vec![format!("[{kind}]")];
+    let text = |value: &Value| {
+        Text::deserialize(value)
+            .map(Text::lines)
+            .unwrap_or_default()
+    };
+    match kind {
+        "stream" => lines.extend(text(&output["text"])),
+        "error" => lines.push(format!(
+            "{}: {}",
+            output["ename"].as_str().unwrap_or_default(),
+            output["evalue"].as_str().unwrap_or_default()
+        )),
+        _ => {
+            let data = output["data"].as_object().into_iter().flatten();
+            for (mime, value) in data {
+                match mime.as_str() {
+                    "text/plain" => lines.extend(text(value)),
+                    // Images and other binary data would only be noise.
+                    _ => lines.push(format!("<{mime}>")),
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Parses the cells of a notebook, keeping the parts that `include` asks
+/// for.
+pub fn parse(text: &str, include: Include) -> serde_json::Result<Vec<Cell>> {
+    let notebook: RawNotebook = serde_json::from_str(text)?;
+    let cells = notebook
+        .cells
+        .into_iter()
+        .map(|cell| {
+            let mut lines = vec![];
+            if include.execution_counts && cell.cell_type == "code" {
+                match cell.execution_count {
+                    Some(count) => lines.push(format!("In [{count}]:")),
+                    None => lines.push("In [ ]:".to_string()),
+                }
+            }
+            let source = cell.source.lines();
+            lines.extend(source.iter().cloned());
+            if include.outputs {
+                lines.extend(cell.outputs.iter().flat_map(output_lines));
+            }
+            Cell {
+                kind: cell.cell_type,
+                source,
+                lines,
+            }
+        })
+        .collect();
+    Ok(cells)
+}
@@ -0,0 +1,269 @@
+//! Settings for computing a diff, gathered in one builder.
+
+use std::hash::Hash;
+
+use crate::{
+    distance_within_weighted,
+    histogram::histogram_with,
+    hook::DiffHook,
+    hunk,
+    intern::Interned,
+    lev_with,
+    myers::{myers, myers_with},
+    normalize::Normalization,
+    patience::patience_with,
+    tokenize, Action, Costs, Hunk,
+};
+
+/// The algorithm that computes the edit script.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// A minimal Levenshtein edit script, see [`lev`](crate::lev).
+    #[default]
+    Levenshtein,
+    /// Myers' O(ND) algorithm, see [`myers`](crate::myers).
+    Myers,
+    /// Anchoring on unique lines first, see [`patience`](crate::patience).
+    Patience,
+    /// Anchoring on the rarest lines first, see
+    /// [`histogram`](crate::histogram).
+    Histogram,
+}
+
+/// How to compute and group a diff.
+///
+/// Every setting has a default, so only the ones that matter need to be
+/// given, as in `DiffOptions::new().algorithm(Algorithm::Myers)`.
+#[derive(Clone, Debug)]
+pub struct DiffOptions {
+    algorithm: Algorithm,
+    normalization: Normalization,
+    context: usize,
+    max_distance: Option<usize>,
+    costs: Costs,
+    similarity_threshold: Option<f64>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        DiffOptions {
+            algorithm: Algorithm::default(),
+            normalization: Normalization::default(),
+            context: 3,
+            max_distance: None,
+            costs: Costs::default(),
+            similarity_threshold: None,
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Returns the default options: the Levenshtein algorithm, exact
+    /// comparison and 3 items of context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the algorithm that computes the edit script.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Sets which differences [`diff`](Self::diff) disregards.
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// Sets how many unchanged items [`hunks`](Self::hunks) keeps around
+    /// each change.
+    pub fn context(mut self, context: usize) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Sets the cost of each kind of change for the Levenshtein algorithm,
+    /// see [`lev_weighted`](crate::lev_weighted).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a cost is larger than [`u32::MAX`].
+    pub fn costs(mut self, costs: Costs) -> Self {
+        let max = u32::MAX as usize;
+        assert!(
+            costs.insert <= max && costs.delete <= max && costs.substitute <= max,
+            "costs must be at most u32::MAX"
+        );
+        self.costs = costs;
+        self
+    }
+
+    /// Sets the largest Levenshtein distance [`try_diff`](Self::try_diff)
+    /// computes an edit script for, or lifts the limit with `None`. The
+    /// distance is the total cost of the changes under the
+    /// [costs](Self::costs).
+    pub fn max_distance(mut self, max_distance: Option<usize>) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Sets how similar two lines must be for [`diff`](Self::diff) to report
+    /// one as substituted by the other, as a ratio between 0 and 1 of the
+    /// words they share. Dissimilar lines are reported as removed and added
+    /// instead. With `None`, any two lines can be paired.
+    pub fn similarity_threshold(mut self, threshold: Option<f64>) -> Self {
+        self.similarity_threshold = threshold;
+        self
+    }
+
+    /// Returns which differences [`diff`](Self::diff) disregards.
+    pub fn get_normalization(&self) -> &Normalization {
+        &self.normalization
+    }
+
+    /// Computes the edit script that turns `old` into `new`, comparing the
+    /// lines under the normalization.
+    pub fn diff(&self, old: &[&str], new: &[&str]) -> Vec<Action> {
+        let mut actions = vec![];
+        self.diff_with(old, new, &mut actions);
+        actions
+    }
+
+    /// Like [`diff`](Self::diff), but passes the edit script to `hook`
+    /// step by step instead of collecting it.
+    ///
+    /// The lines are [interned](Interned) by their normalized form first, so
+    /// the algorithms compare integers rather than strings.
+    pub fn diff_with<H: DiffHook + ?Sized>(&self, old: &[&str], new: &[&str], hook: &mut H) {
+        let interned = self.intern(old, new);
+        self.diff_interned(old, new, &interned, hook);
+    }
+
+    /// Like [`diff`](Self::diff), but returns `None` without computing the
+    /// edit script when the lines are further apart than the
+    /// [maximum distance](Self::max_distance).
+    pub fn try_diff(&self, old: &[&str], new: &[&str]) -> Option<Vec<Action>> {
+        let interned = self.intern(old, new);
+        if let Some(max) = self.max_distance {
+            distance_within_weighted(&interned.old, &interned.new, max, &self.costs)?;
+        }
+        let mut actions = vec![];
+        self.diff_interned(old, new, &interned, &mut actions);
+        Some(actions)
+    }
+
+    fn diff_interned<H: DiffHook + ?Sized>(
+        &self,
+        old: &[&str],
+        new: &[&str],
+        interned: &Interned,
+        hook: &mut H,
+    ) {
+        let Some(threshold) = self.similarity_threshold else {
+            self.diff_items_with(&interned.old, &interned.new, hook);
+            return;
+        };
+        let mut pairing = Pairing {
+            hook,
+            old,
+            new,
+            threshold,
+        };
+        self.diff_items_with(&interned.old, &interned.new, &mut pairing);
+    }
+
+    fn intern(&self, old: &[&str], new: &[&str]) -> Interned {
+        let key = |&line| self.normalization.key(line);
+        let old = old.iter().map(key).collect::<Vec<_>>();
+        let new = new.iter().map(key).collect::<Vec<_>>();
+        Interned::new(&old, &new)
+    }
+
+    /// Computes the edit script that turns `s1` into `s2`, comparing the
+    /// items as they are.
+    pub fn diff_items<T: Hash + Eq>(&self, s1: &[T], s2: &[T]) -> Vec<Action> {
+        let mut actions = vec![];
+        self.diff_items_with(s1, s2, &mut actions);
+        actions
+    }
+
+    /// Like [`diff_items`](Self::diff_items), but passes the edit script to
+    /// `hook` step by step instead of collecting it.
+    pub fn diff_items_with<T: Hash + Eq, H: DiffHook + ?Sized>(
+        &self,
+        s1: &[T],
+        s2: &[T],
+        hook: &mut H,
+    ) {
+        match self.algorithm {
+            Algorithm::Levenshtein => lev_with(s1, s2, &self.costs, hook),
+            Algorithm::Myers => myers_with(s1, s2, hook),
+            Algorithm::Patience => patience_with(s1, s2, hook),
+            Algorithm::Histogram => histogram_with(s1, s2, hook),
+        }
+    }
+
+    /// Groups `actions` into hunks with the configured context.
+    pub fn hunks<'a>(&self, actions: &'a [Action]) -> Vec<Hunk<'a>> {
+        hunk::hunks(actions, self.context)
+    }
+}
+
+/// Returns the share of words `line1` and `line2` have in common: twice the
+/// length of their longest common subsequence over their combined length,
+/// not counting whitespace.
+fn overlap(line1: &str, line2: &str) -> f64 {
+    let words = |line| {
+        let words = tokenize::words(line).into_iter();
+        words
+            .filter(|word| !word.trim().is_empty())
+            .collect::<Vec<_>>()
+    };
+    let (words1, words2) = (words(line1), words(line2));
+    let total = words1.len() + words2.len();
+    if total == 0 {
+        return 1.0;
+    }
+    let common = myers(&words1, &words2)
+        .iter()
+        .filter(|action| !action.is_change())
+        .count();
+    2.0 * common as f64 / total as f64
+}
+
+/// Passes an edit script on to `hook`, splitting the replacements of lines
+/// that overlap less than `threshold` into a deletion and an insertion.
+struct Pairing<'a, 'b, H: ?Sized> {
+    hook: &'a mut H,
+    old: &'a [&'b str],
+    new: &'a [&'b str],
+    threshold: f64,
+}
+
+impl<H: DiffHook + ?Sized> DiffHook for Pairing<'_, '_, H> {
+    fn on_equal(&mut self, old: usize, new: usize) {
+        self.hook.on_equal(old, new);
+    }
+
+    fn on_insert(&mut self, old: usize, new: usize) {
+        self.hook.on_insert(old, new);
+    }
+
+    fn on_delete(&mut self, old: usize, new: usize) {
+        self.hook.on_delete(old, new);
+    }
+
+    fn on_replace(&mut self, old: usize, new: usize) {
+        if overlap(self.old[old], self.new[new]) >= self.threshold {
+            self.hook.on_replace(old, new);
+        } else {
+            self.hook.on_delete(old, new);
+            self.hook.on_insert(old + 1, new);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.hook.finish();
+    }
+}
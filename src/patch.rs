@@ -0,0 +1,283 @@
+//! Parsing saved diffs and applying them to files.
+
+use std::{error, fmt};
+
+use crate::{Action, Diff};
+
+/// A line of a hunk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Line {
+    /// A line that must be present and is kept.
+    Context(String),
+    /// A line that must be present and is removed.
+    Remove(String),
+    /// A line that is inserted.
+    Add(String),
+}
+
+/// A contiguous region of changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    /// The 1-based line of the old file where the hunk starts, or the line
+    /// after which it is inserted when it covers no old lines.
+    pub old_start: usize,
+    /// The lines of the hunk, in order.
+    pub lines: Vec<Line>,
+    /// Whether the last old and the last new line of the hunk are the last
+    /// lines of their file and have no newline after them.
+    pub no_newline: (bool, bool),
+}
+
+impl Hunk {
+    /// Returns the lines the hunk expects to find in the old file.
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                Line::Context(text) | Line::Remove(text) => Some(text.as_str()),
+                Line::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Records a `\ No newline at end of file` marker, which is about the
+    /// line before it.
+    fn mark_no_newline(&mut self) {
+        match self.lines.last() {
+            Some(Line::Context(_)) => self.no_newline = (true, true),
+            Some(Line::Remove(_)) => self.no_newline.0 = true,
+            Some(Line::Add(_)) => self.no_newline.1 = true,
+            None => {}
+        }
+    }
+
+    /// Parses hunk lines edited by hand, such as the [`Display`](fmt::Display)
+    /// output of a hunk. Blank lines are taken as empty context lines and
+    /// lines starting with `#` are skipped.
+    pub fn parse(old_start: usize, text: &str) -> Result<Self, Error> {
+        let mut hunk = Hunk {
+            old_start,
+            lines: vec![],
+            no_newline: (false, false),
+        };
+        for (n, line) in text.lines().enumerate() {
+            let (tag, text) = line.split_at(line.len().min(1));
+            let text = text.to_string();
+            match tag {
+                "#" => {}
+                "\\" => hunk.mark_no_newline(),
+                " " | "" => hunk.lines.push(Line::Context(text)),
+                "-" => hunk.lines.push(Line::Remove(text)),
+                "+" => hunk.lines.push(Line::Add(text)),
+                _ => {
+                    return Err(Error::new(format!(
+                        "line {}: unexpected line in hunk",
+                        n + 1
+                    )))
+                }
+            }
+        }
+        Ok(hunk)
+    }
+}
+
+impl fmt::Display for Hunk {
+    /// Writes the lines of the hunk as they appear in a unified diff,
+    /// without the `@@` header.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let last_old = self
+            .lines
+            .iter()
+            .rposition(|line| !matches!(line, Line::Add(_)));
+        let last_new = self
+            .lines
+            .iter()
+            .rposition(|line| !matches!(line, Line::Remove(_)));
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                Line::Context(text) => writeln!(f, " {text}")?,
+                Line::Remove(text) => writeln!(f, "-{text}")?,
+                Line::Add(text) => writeln!(f, "+{text}")?,
+            }
+            if self.no_newline.0 && last_old == Some(i) || self.no_newline.1 && last_new == Some(i)
+            {
+                writeln!(f, "\\ No newline at end of file")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed diff, ready to be applied.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    /// The hunks of the patch, ordered by position.
+    pub hunks: Vec<Hunk>,
+}
+
+/// An error while parsing or applying a patch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    fn new(message: impl Into<String>) -> Self {
+        Error {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+/// Parses a `start,count` or `start` hunk range.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+/// Parses the `@@ -a,b +c,d @@` header of a hunk.
+fn parse_header(line: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = line.strip_prefix("@@ ")?.split(' ');
+    let old = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let new = parse_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+impl Patch {
+    /// Builds a patch from the complete edit script of `diff`, such as the
+    /// one returned by [`lev`](crate::lev).
+    pub fn from_actions(diff: &Diff<&str>) -> Self {
+        let mut lines = vec![];
+        for action in diff.actions {
+            match *action {
+                Action::Add(_, j) => lines.push(Line::Add(diff.new[j].to_string())),
+                Action::Remove(i, _) => lines.push(Line::Remove(diff.old[i].to_string())),
+                Action::Substitute(i, j) => {
+                    lines.push(Line::Remove(diff.old[i].to_string()));
+                    lines.push(Line::Add(diff.new[j].to_string()));
+                }
+                Action::Ignore(i, _) => lines.push(Line::Context(diff.old[i].to_string())),
+            }
+        }
+        let old_start = usize::from(lines.iter().any(|line| !matches!(line, Line::Add(_))));
+        Patch {
+            hunks: vec![Hunk {
+                old_start,
+                lines,
+                no_newline: (false, false),
+            }],
+        }
+    }
+
+    /// Parses a unified diff. Everything outside of hunks, such as the file
+    /// headers, is skipped.
+    pub fn parse_unified(text: &str) -> Result<Self, Error> {
+        let mut hunks = vec![];
+        let mut lines = text.lines().enumerate().peekable();
+        while let Some((n, line)) = lines.next() {
+            if !line.starts_with("@@") {
+                continue;
+            }
+            let ((old_start, mut old_count), (_, mut new_count)) = parse_header(line)
+                .ok_or_else(|| Error::new(format!("line {}: malformed hunk header", n + 1)))?;
+            let mut hunk = Hunk {
+                old_start,
+                lines: vec![],
+                no_newline: (false, false),
+            };
+            while old_count > 0 || new_count > 0 {
+                let Some((n, line)) = lines.next() else {
+                    return Err(Error::new("unexpected end of patch inside a hunk"));
+                };
+                let (tag, text) = line.split_at(line.len().min(1));
+                let text = text.to_string();
+                match tag {
+                    " " | "" if old_count > 0 && new_count > 0 => {
+                        old_count -= 1;
+                        new_count -= 1;
+                        hunk.lines.push(Line::Context(text));
+                    }
+                    "-" if old_count > 0 => {
+                        old_count -= 1;
+                        hunk.lines.push(Line::Remove(text));
+                    }
+                    "+" if new_count > 0 => {
+                        new_count -= 1;
+                        hunk.lines.push(Line::Add(text));
+                    }
+                    "\\" => hunk.mark_no_newline(),
+                    _ => {
+                        return Err(Error::new(format!(
+                            "line {}: unexpected line in hunk",
+                            n + 1
+                        )))
+                    }
+                }
+            }
+            while lines.next_if(|(_, line)| line.starts_with('\\')).is_some() {
+                hunk.mark_no_newline();
+            }
+            hunks.push(hunk);
+        }
+        Ok(Patch { hunks })
+    }
+
+    /// Applies the patch to `lines` and returns the patched lines.
+    ///
+    /// Like `patch`, a hunk whose lines are not found at the recorded
+    /// position is looked for at the nearest offset where they match.
+    pub fn apply(&self, lines: &[&str]) -> Result<Vec<String>, Error> {
+        let mut result = vec![];
+        let mut pos = 0;
+        let mut offset = 0isize;
+        for (i, hunk) in self.hunks.iter().enumerate() {
+            let old = hunk.old_lines();
+            let expected = match old.is_empty() {
+                true => hunk.old_start,
+                false => hunk.old_start.saturating_sub(1),
+            };
+            let expected = expected.saturating_add_signed(offset).max(pos);
+            let matches = |at: usize| lines.get(at..at + old.len()) == Some(&old[..]);
+            let start = (0..lines.len().max(expected) + 1)
+                .flat_map(|delta| [expected.checked_add(delta), expected.checked_sub(delta)])
+                .flatten()
+                .filter(|&at| at >= pos)
+                .find(|&at| matches(at))
+                .ok_or_else(|| Error::new(format!("hunk {} does not apply", i + 1)))?;
+            offset += start as isize - expected as isize;
+            result.extend(lines[pos..start].iter().map(|line| line.to_string()));
+            for line in &hunk.lines {
+                match line {
+                    Line::Context(text) | Line::Add(text) => result.push(text.clone()),
+                    Line::Remove(_) => {}
+                }
+            }
+            pos = start + old.len();
+        }
+        result.extend(lines[pos..].iter().map(|line| line.to_string()));
+        Ok(result)
+    }
+
+    /// Returns whether the patched file ends with a newline, given whether
+    /// the file the patch is applied to does: only a hunk that marks the
+    /// last line of the old or the new file changes that.
+    pub fn ends_with_newline(&self, original: bool) -> bool {
+        if self.hunks.iter().any(|hunk| hunk.no_newline.1) {
+            false
+        } else if self.hunks.iter().any(|hunk| hunk.no_newline.0) {
+            true
+        } else {
+            original
+        }
+    }
+}
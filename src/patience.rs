@@ -0,0 +1,112 @@
+//! Patience diff, which lines up the items that occur once in both inputs
+//! before diffing the gaps between them.
+//!
+//! Unique lines are usually the meaningful ones, such as function
+//! signatures, so this keeps blank lines and braces from being matched
+//! across unrelated parts of a file, as `git diff --patience` does.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+    edit::{self, Edit},
+    hook::DiffHook,
+    myers, trim, Action,
+};
+
+/// Returns the longest sequence of index pairs of items that occur exactly
+/// once in both `old` and `new` and appear in the same order in both.
+pub(crate) fn anchors<T: Hash + Eq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    // For each item, its position in old and new, or None once it repeats.
+    let mut positions = HashMap::new();
+    for (i, item) in old.iter().enumerate() {
+        positions
+            .entry(item)
+            .and_modify(|entry: &mut (Option<usize>, Option<usize>)| entry.0 = None)
+            .or_insert((Some(i), None));
+    }
+    for (j, item) in new.iter().enumerate() {
+        if let Some(entry) = positions.get_mut(item) {
+            entry.1 = match entry.1 {
+                None if entry.0.is_some() => Some(j),
+                _ => {
+                    entry.0 = None;
+                    None
+                }
+            };
+        }
+    }
+    let mut pairs = positions
+        .into_values()
+        .filter_map(|(i, j)| Some((i?, j?)))
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+
+    // Patience sorting: `tails[k]` is the pair ending the smallest-ended
+    // increasing run of length k + 1, and `prev` links each pair to the one
+    // before it in its run.
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; pairs.len()];
+    for (n, &(_, j)) in pairs.iter().enumerate() {
+        let k = tails.partition_point(|&t| pairs[t].1 < j);
+        prev[n] = k.checked_sub(1).map(|k| tails[k]);
+        match tails.get_mut(k) {
+            Some(tail) => *tail = n,
+            None => tails.push(n),
+        }
+    }
+    let mut result = vec![];
+    let mut next = tails.last().copied();
+    while let Some(n) = next {
+        result.push(pairs[n]);
+        next = prev[n];
+    }
+    result.reverse();
+    result
+}
+
+/// Appends the edit script of `s1` and `s2`, which start at `offset` in the
+/// whole inputs, to `out`.
+fn align<T: Hash + Eq>(s1: &[T], s2: &[T], (o1, o2): (usize, usize), out: &mut Vec<Edit>) {
+    let (prefix, suffix) = trim::common(s1, s2);
+    out.extend((0..prefix).map(|k| Edit::Equal(o1 + k, o2 + k)));
+    let (end1, end2) = (s1.len() - suffix, s2.len() - suffix);
+    let (m1, m2) = (&s1[prefix..end1], &s2[prefix..end2]);
+    let (o1, o2) = (o1 + prefix, o2 + prefix);
+    if !m1.is_empty() || !m2.is_empty() {
+        let anchors = anchors(m1, m2);
+        if anchors.is_empty() {
+            // Nothing to anchor on: fall back to a plain minimal diff.
+            let edits = myers::edits(m1, m2);
+            out.extend(edits.into_iter().map(|edit| edit.shift(o1, o2)));
+        } else {
+            let (mut i, mut j) = (0, 0);
+            for (ai, aj) in anchors {
+                align(&m1[i..ai], &m2[j..aj], (o1 + i, o2 + j), out);
+                out.push(Edit::Equal(o1 + ai, o2 + aj));
+                (i, j) = (ai + 1, aj + 1);
+            }
+            align(&m1[i..], &m2[j..], (o1 + i, o2 + j), out);
+        }
+    }
+    out.extend((0..suffix).map(|k| Edit::Equal(o1 + m1.len() + k, o2 + m2.len() + k)));
+}
+
+/// Computes the edit script that turns `s1` into `s2` with the patience
+/// algorithm.
+///
+/// The result is not always minimal, but tends to read better for source
+/// code. Gaps without unique items are diffed with
+/// [`myers`](crate::myers), and neighbouring removals and additions are
+/// reported as [`Action::Substitute`] in the same way.
+pub fn patience<T: Hash + Eq>(s1: &[T], s2: &[T]) -> Vec<Action> {
+    let mut actions = vec![];
+    patience_with(s1, s2, &mut actions);
+    actions
+}
+
+/// Like [`patience`], but passes the edit script to `hook` instead.
+pub(crate) fn patience_with<T: Hash + Eq, H: DiffHook + ?Sized>(s1: &[T], s2: &[T], hook: &mut H) {
+    let mut edits = Vec::with_capacity(s1.len().max(s2.len()));
+    align(s1, s2, (0, 0), &mut edits);
+    edit::replay(&edits, true, hook);
+}
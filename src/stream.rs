@@ -0,0 +1,129 @@
+//! Diffing inputs too large to hold in memory, a window of lines at a time.
+//!
+//! Both inputs are read into windows of a fixed number of lines. Lines that
+//! occur exactly once in each window are taken as anchors: the longest run
+//! of them in the same order on both sides is assumed to line up, the lines
+//! between two anchors are diffed on their own, and every line up to the
+//! last anchor is dropped before reading more. The result is a valid edit
+//! script, though not always a minimal one.
+
+use std::io::{self, BufRead};
+
+use crate::{input, options::DiffOptions, patience, Diff};
+
+/// Lines read from one input that are not diffed yet.
+struct Window<R> {
+    reader: R,
+    lines: Vec<String>,
+    /// The number of lines of the input dropped from the window so far.
+    start: usize,
+    eof: bool,
+}
+
+impl<R: BufRead> Window<R> {
+    fn new(reader: R) -> Self {
+        Window {
+            reader,
+            lines: vec![],
+            start: 0,
+            eof: false,
+        }
+    }
+
+    /// Reads lines until the window holds `len` of them or the input ends.
+    ///
+    /// Line endings are stripped like [`str::lines`] does. Lines that are
+    /// not valid UTF-8 are decoded through [`input::escape`].
+    fn fill(&mut self, len: usize) -> io::Result<()> {
+        let mut buf = vec![];
+        while !self.eof && self.lines.len() < len {
+            buf.clear();
+            if self.reader.read_until(b'\n', &mut buf)? == 0 {
+                self.eof = true;
+                break;
+            }
+            if buf.ends_with(b"\n") {
+                buf.pop();
+                if buf.ends_with(b"\r") {
+                    buf.pop();
+                }
+            }
+            self.lines.push(match std::str::from_utf8(&buf) {
+                Ok(line) => line.to_string(),
+                Err(_) => input::escape(&buf),
+            });
+        }
+        Ok(())
+    }
+
+    fn drop_lines(&mut self, len: usize) {
+        self.lines.drain(..len);
+        self.start += len;
+    }
+}
+
+/// Returns the anchors of the windows, see [`patience::anchors`], comparing
+/// the lines by their key under `options`.
+fn anchors(old: &[String], new: &[String], options: &DiffOptions) -> Vec<(usize, usize)> {
+    let normalization = options.get_normalization();
+    let old = old
+        .iter()
+        .map(|line| normalization.key(line))
+        .collect::<Vec<_>>();
+    let new = new
+        .iter()
+        .map(|line| normalization.key(line))
+        .collect::<Vec<_>>();
+    patience::anchors(&old, &new)
+}
+
+/// Diffs the lines of `old` and `new`, holding at most about `window` lines
+/// of each in memory.
+///
+/// The edit script is passed to `f` in pieces, each along with the number of
+/// old and new lines that precede it. The pieces use `options` but are only
+/// minimal within themselves.
+pub fn diff_chunks<R1, R2, F>(
+    old: R1,
+    new: R2,
+    window: usize,
+    options: &DiffOptions,
+    mut f: F,
+) -> io::Result<()>
+where
+    R1: BufRead,
+    R2: BufRead,
+    F: FnMut(&Diff<&str>, (usize, usize)) -> io::Result<()>,
+{
+    let (mut old, mut new) = (Window::new(old), Window::new(new));
+    loop {
+        old.fill(window)?;
+        new.fill(window)?;
+        if old.lines.is_empty() && new.lines.is_empty() {
+            return Ok(());
+        }
+        let mut cuts = anchors(&old.lines, &new.lines, options)
+            .into_iter()
+            .map(|(i, j)| (i + 1, j + 1))
+            .collect::<Vec<_>>();
+        if cuts.is_empty() || old.eof && new.eof {
+            cuts.push((old.lines.len(), new.lines.len()));
+        }
+        let (mut start1, mut start2) = (0, 0);
+        for (end1, end2) in cuts {
+            let lines1 = old.lines[start1..end1].iter().map(String::as_str);
+            let lines2 = new.lines[start2..end2].iter().map(String::as_str);
+            let (lines1, lines2) = (lines1.collect::<Vec<_>>(), lines2.collect::<Vec<_>>());
+            let actions = options.diff(&lines1, &lines2);
+            let diff = Diff {
+                old: &lines1,
+                new: &lines2,
+                actions: &actions,
+            };
+            f(&diff, (old.start + start1, new.start + start2))?;
+            (start1, start2) = (end1, end2);
+        }
+        old.drop_lines(start1);
+        new.drop_lines(start2);
+    }
+}
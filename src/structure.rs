@@ -0,0 +1,507 @@
+//! Structural diffs of parsed documents, which compare data rather than the
+//! lines it happens to be formatted on.
+
+use std::{collections::BTreeSet, error, fmt};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{lev, Action};
+
+/// A step from a value into one of its children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// The value of a key of an object.
+    Key(String),
+    /// The element at a 0-based index of an array.
+    Index(usize),
+}
+
+/// Where a value lies within a document, as the steps from its root.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Location(pub Vec<Segment>);
+
+impl Location {
+    fn child(&self, segment: Segment) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(segment);
+        Location(segments)
+    }
+
+    /// Formats the location as a dotted path of keys like TOML does, as
+    /// `server.port` or `servers[3].name`, quoting keys that are not bare
+    /// keys, or `.` for the root.
+    pub fn dotted(&self) -> String {
+        if self.0.is_empty() {
+            return ".".to_string();
+        }
+        let mut path = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Key(key) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    if is_bare(key) {
+                        path.push_str(key);
+                    } else {
+                        path.push_str(&Value::from(key.as_str()).to_string());
+                    }
+                }
+                Segment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+}
+
+impl fmt::Display for Location {
+    /// Formats the location like `jq` does, as `.key[3]`, quoting keys that
+    /// are not identifiers, or `.` for the root.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, ".");
+        }
+        for segment in &self.0 {
+            match segment {
+                Segment::Key(key) if is_identifier(key) => write!(f, ".{key}")?,
+                Segment::Key(key) => write!(f, "[{}]", Value::from(key.as_str()))?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_bare(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// A difference between two documents.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A key or an array element only the new document has.
+    Added(Location, Value),
+    /// A key or an array element only the old document has.
+    Removed(Location, Value),
+    /// A value that was replaced, from the old to the new one.
+    Changed(Location, Value, Value),
+}
+
+impl Change {
+    /// Replaces the values of the change by a placeholder, to hide secrets.
+    pub fn mask(&mut self) {
+        let masked = || Value::from("***");
+        match self {
+            Change::Added(_, value) | Change::Removed(_, value) => *value = masked(),
+            Change::Changed(_, old, new) => (*old, *new) = (masked(), masked()),
+        }
+    }
+}
+
+/// An error while reading a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    fn new(message: impl Into<String>) -> Self {
+        Error {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+/// Parses a `.env` file of `KEY=value` lines into an object from the keys
+/// to their values.
+///
+/// Blank lines and comments are skipped, as is an `export` before a key.
+/// Values may be quoted, and escapes are only interpreted within double
+/// quotes. A key that is set twice keeps its last value, as in a shell.
+pub fn from_dotenv(text: &str) -> Result<Value, Error> {
+    let mut map = Map::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| Error::new(format!("line {}: expected `KEY=value`", n + 1)))?;
+        map.insert(
+            key.trim().to_string(),
+            Value::from(dotenv_value(value.trim())),
+        );
+    }
+    Ok(Value::Object(map))
+}
+
+/// Unquotes the value of a `.env` line, or drops a comment after it.
+fn dotenv_value(value: &str) -> String {
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest.split('\'').next().unwrap_or_default().to_string();
+    }
+    let Some(rest) = value.strip_prefix('"') else {
+        let value = value.split(" #").next().unwrap_or_default();
+        return value.trim_end().to_string();
+    };
+    let mut result = String::new();
+    let mut chars = rest.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(c) => result.push(c),
+                None => break,
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Parses an INI file into an object from the section names to objects
+/// from their keys to their values.
+///
+/// Keys before the first section are kept at the top level. Sections that
+/// appear twice are merged, and lines starting with `;` or `#` are
+/// comments. Keys are separated from values by `=` or `:`.
+pub fn from_ini(text: &str) -> Result<Value, Error> {
+    let mut root = Map::new();
+    let mut section = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let name = name.trim().to_string();
+            root.entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            section = Some(name);
+            continue;
+        }
+        let (key, value) = line
+            .split_once(['=', ':'])
+            .ok_or_else(|| Error::new(format!("line {}: expected `key = value`", n + 1)))?;
+        let map = match &section {
+            Some(name) => match &mut root[name] {
+                Value::Object(map) => map,
+                _ => {
+                    return Err(Error::new(format!(
+                        "line {}: `{name}` is both a key and a section",
+                        n + 1
+                    )))
+                }
+            },
+            None => &mut root,
+        };
+        map.insert(key.trim().to_string(), Value::from(value.trim()));
+    }
+    Ok(Value::Object(root))
+}
+
+/// Parses a CSV table with a header row into a value.
+///
+/// Every row becomes an object from the column names to its cells. The rows
+/// make up an array, or with a `key` column an object from the cell of that
+/// column to the row, so that reordered rows compare equal.
+pub fn from_csv(text: &str, key: Option<&str>) -> Result<Value, Error> {
+    let error = |err: csv::Error| Error::new(err.to_string());
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(error)?.clone();
+    let key = key
+        .map(|key| {
+            let column = headers.iter().position(|header| header == key);
+            column.ok_or_else(|| Error::new(format!("no column named `{key}`")))
+        })
+        .transpose()?;
+    let mut rows = vec![];
+    let mut keyed = Map::new();
+    for record in reader.records() {
+        let record = record.map_err(error)?;
+        let row = headers
+            .iter()
+            .zip(&record)
+            .map(|(header, cell)| (header.to_string(), Value::from(cell)))
+            .collect::<Map<_, _>>();
+        match key {
+            Some(column) => {
+                let key = record.get(column).unwrap_or_default().to_string();
+                if keyed.insert(key.clone(), Value::Object(row)).is_some() {
+                    return Err(Error::new(format!("duplicate key `{key}`")));
+                }
+            }
+            None => rows.push(Value::Object(row)),
+        }
+    }
+    match key {
+        Some(_) => Ok(Value::Object(keyed)),
+        None => Ok(Value::Array(rows)),
+    }
+}
+
+/// Formats an XML document canonically, with every element, text and
+/// comment on a line of its own, indented by its depth.
+///
+/// Whitespace around text is dropped and attributes are sorted by name, so
+/// that documents that only differ in formatting come out the same. The
+/// declaration and processing instructions are left out.
+pub fn canonical_xml(text: &str) -> Result<String, Error> {
+    let document = roxmltree::Document::parse(text).map_err(|err| Error::new(err.to_string()))?;
+    let mut result = String::new();
+    write_node(document.root_element(), 0, &mut result);
+    Ok(result)
+}
+
+/// Returns the attributes of an element as `name="value"`, sorted by name.
+fn attributes(node: roxmltree::Node) -> Vec<String> {
+    let mut attributes = node
+        .attributes()
+        .map(|attribute| {
+            let value = attribute
+                .value()
+                .replace('&', "&amp;")
+                .replace('"', "&quot;");
+            format!("{}=\"{value}\"", attribute.name())
+        })
+        .collect::<Vec<_>>();
+    attributes.sort();
+    attributes
+}
+
+fn write_node(node: roxmltree::Node, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    match node.node_type() {
+        roxmltree::NodeType::Element => {
+            let name = node.tag_name().name();
+            let mut start = format!("{indent}<{name}");
+            for attribute in attributes(node) {
+                start.push(' ');
+                start.push_str(&attribute);
+            }
+            let children = node.children().filter(|child| !is_blank(*child));
+            if children.clone().next().is_none() {
+                out.push_str(&format!("{start}/>\n"));
+                return;
+            }
+            out.push_str(&format!("{start}>\n"));
+            for child in children {
+                write_node(child, depth + 1, out);
+            }
+            out.push_str(&format!("{indent}</{name}>\n"));
+        }
+        roxmltree::NodeType::Text => {
+            for line in node.text().unwrap_or_default().trim().lines() {
+                out.push_str(&format!("{indent}{}\n", line.trim()));
+            }
+        }
+        roxmltree::NodeType::Comment => {
+            let comment = node.text().unwrap_or_default().trim();
+            out.push_str(&format!("{indent}<!-- {comment} -->\n"));
+        }
+        roxmltree::NodeType::Root | roxmltree::NodeType::PI => {}
+    }
+}
+
+/// Returns whether a node carries no content, like the whitespace between
+/// elements.
+fn is_blank(node: roxmltree::Node) -> bool {
+    match node.node_type() {
+        roxmltree::NodeType::Text => node.text().unwrap_or_default().trim().is_empty(),
+        roxmltree::NodeType::PI => true,
+        _ => false,
+    }
+}
+
+/// Parses the element tree of an XML document into a value.
+///
+/// Every element becomes an object with its name under `tag`, each of its
+/// attributes under its name prefixed with `@` and, if it has any, its child
+/// elements and texts under `children`. Whitespace around text is dropped,
+/// and comments and processing instructions are left out.
+pub fn from_xml(text: &str) -> Result<Value, Error> {
+    let document = roxmltree::Document::parse(text).map_err(|err| Error::new(err.to_string()))?;
+    Ok(element_to_json(document.root_element()))
+}
+
+fn element_to_json(node: roxmltree::Node) -> Value {
+    let mut map = Map::new();
+    map.insert("tag".to_string(), Value::from(node.tag_name().name()));
+    for attribute in node.attributes() {
+        map.insert(
+            format!("@{}", attribute.name()),
+            Value::from(attribute.value()),
+        );
+    }
+    let children = node
+        .children()
+        .filter_map(|child| match child.node_type() {
+            roxmltree::NodeType::Element => Some(element_to_json(child)),
+            roxmltree::NodeType::Text => {
+                let text = child.text().unwrap_or_default().trim();
+                (!text.is_empty()).then(|| Value::from(text))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !children.is_empty() {
+        map.insert("children".to_string(), Value::Array(children));
+    }
+    Value::Object(map)
+}
+
+/// Parses a YAML stream into a value.
+///
+/// A stream of several documents, as is common for Kubernetes manifests,
+/// becomes an array of them, and an empty one null. Keys that are not
+/// strings are formatted as YAML, and tags are dropped.
+pub fn from_yaml(text: &str) -> Result<Value, serde_yaml::Error> {
+    let mut documents = serde_yaml::Deserializer::from_str(text)
+        .map(|document| serde_yaml::Value::deserialize(document).map(yaml_to_json))
+        .collect::<Result<Vec<_>, _>>()?;
+    match documents.len() {
+        0 => Ok(Value::Null),
+        1 => Ok(documents.remove(0)),
+        _ => Ok(Value::Array(documents)),
+    }
+}
+
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(bool) => Value::Bool(bool),
+        serde_yaml::Value::Number(number) => match number.as_i64() {
+            Some(int) => Value::from(int),
+            None => match number.as_u64() {
+                Some(uint) => Value::from(uint),
+                None => Value::from(number.as_f64()),
+            },
+        },
+        serde_yaml::Value::String(string) => Value::String(string),
+        serde_yaml::Value::Sequence(items) => {
+            Value::Array(items.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) => key,
+                    key => serde_yaml::to_string(&key)
+                        .unwrap_or_default()
+                        .trim_end()
+                        .to_string(),
+                };
+                map.insert(key, yaml_to_json(value));
+            }
+            Value::Object(map)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// Parses a TOML document into a value, with dates and times as strings.
+pub fn from_toml(text: &str) -> Result<Value, toml::de::Error> {
+    let table = toml::from_str::<toml::Table>(text)?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(string) => Value::String(string),
+        toml::Value::Integer(int) => Value::from(int),
+        toml::Value::Float(float) => Value::from(float),
+        toml::Value::Boolean(bool) => Value::Bool(bool),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Returns the differences between `old` and `new`, in document order.
+///
+/// Objects are compared key by key, so the order of their keys doesn't
+/// matter. Arrays are aligned with [`lev`], and elements that were
+/// substituted by another of the same kind are compared in turn. Locations
+/// within arrays use the index in the old document for removed elements and
+/// the one in the new document otherwise.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_values(&Location::default(), old, new, &mut changes);
+    changes
+}
+
+fn diff_values(location: &Location, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        _ if old == new => {}
+        (Value::Object(map1), Value::Object(map2)) => {
+            let keys = map1.keys().chain(map2.keys()).collect::<BTreeSet<_>>();
+            for key in keys {
+                let child = location.child(Segment::Key(key.clone()));
+                match (map1.get(key), map2.get(key)) {
+                    (Some(value1), Some(value2)) => diff_values(&child, value1, value2, changes),
+                    (Some(value), None) => changes.push(Change::Removed(child, value.clone())),
+                    (None, Some(value)) => changes.push(Change::Added(child, value.clone())),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (Value::Array(items1), Value::Array(items2)) => {
+            // Compare elements by their serialization, since numbers don't
+            // implement `Eq`.
+            let keys1 = items1.iter().map(Value::to_string).collect::<Vec<_>>();
+            let keys2 = items2.iter().map(Value::to_string).collect::<Vec<_>>();
+            for action in lev(&keys1, &keys2) {
+                match action {
+                    Action::Add(_, j) => changes.push(Change::Added(
+                        location.child(Segment::Index(j)),
+                        items2[j].clone(),
+                    )),
+                    Action::Remove(i, _) => changes.push(Change::Removed(
+                        location.child(Segment::Index(i)),
+                        items1[i].clone(),
+                    )),
+                    Action::Substitute(i, j) => diff_values(
+                        &location.child(Segment::Index(j)),
+                        &items1[i],
+                        &items2[j],
+                        changes,
+                    ),
+                    Action::Ignore(..) => {}
+                }
+            }
+        }
+        _ => changes.push(Change::Changed(location.clone(), old.clone(), new.clone())),
+    }
+}
@@ -0,0 +1,67 @@
+//! Syntax highlighting of lines with syntect.
+
+use std::sync::OnceLock;
+
+use ansi_term::{Color, Style};
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{self, FontStyle, Theme, ThemeSet},
+    parsing::SyntaxSet,
+};
+
+/// The syntect theme the lines are highlighted with.
+const THEME: &str = "base16-ocean.dark";
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_nonewlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+fn style(style: highlighting::Style) -> Style {
+    let color = style.foreground;
+    let mut result = Style::new().fg(Color::RGB(color.r, color.g, color.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        result = result.bold();
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        result = result.italic();
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        result = result.underline();
+    }
+    result
+}
+
+/// Returns whether `language` names a language that can be highlighted,
+/// either by its name such as `Rust` or by a file extension such as `rs`.
+pub fn is_known(language: &str) -> bool {
+    syntaxes().find_syntax_by_token(language).is_some()
+}
+
+/// Highlights `lines` as the successive lines of a file in `language`, see
+/// [`is_known`], and returns the styled spans of every line.
+///
+/// Returns `None` if the language is unknown or its grammar fails on the
+/// lines.
+pub fn highlight<'a>(language: &str, lines: &[&'a str]) -> Option<Vec<Vec<(Style, &'a str)>>> {
+    let syntaxes = syntaxes();
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    lines
+        .iter()
+        .map(|line| {
+            let spans = highlighter.highlight_line(line, syntaxes).ok()?;
+            Some(
+                spans
+                    .into_iter()
+                    .map(|(s, text)| (style(s), text))
+                    .collect(),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,52 @@
+//! Splitting text into tokens for sub-line diffs.
+
+#[derive(PartialEq, Eq)]
+enum Class {
+    Word,
+    Space,
+    Other,
+}
+
+fn class(c: char) -> Class {
+    if c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else if c.is_whitespace() {
+        Class::Space
+    } else {
+        Class::Other
+    }
+}
+
+/// Splits `s` into words, whitespace runs and single punctuation
+/// characters.
+///
+/// Concatenating the tokens yields `s` again, so a token diff can be
+/// rendered without losing any of the original text.
+pub fn words(s: &str) -> Vec<&str> {
+    let mut result = vec![];
+    let mut start = 0;
+    let mut prev = None;
+    for (i, c) in s.char_indices() {
+        let class = class(c);
+        let merge = match &prev {
+            Some(prev) => *prev == class && class != Class::Other,
+            None => true,
+        };
+        if !merge {
+            result.push(&s[start..i]);
+            start = i;
+        }
+        prev = Some(class);
+    }
+    if start < s.len() {
+        result.push(&s[start..]);
+    }
+    result
+}
+
+/// Splits `s` into its characters.
+pub fn chars(s: &str) -> Vec<&str> {
+    s.char_indices()
+        .map(|(i, c)| &s[i..i + c.len_utf8()])
+        .collect()
+}
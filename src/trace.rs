@@ -0,0 +1,42 @@
+//! Tracing of how edit scripts are computed, written to stderr.
+
+use std::{
+    sync::atomic::{AtomicU8, Ordering},
+    time::Instant,
+};
+
+/// Something that can be traced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trace {
+    /// The final cost matrix of every Levenshtein alignment.
+    Matrix = 1,
+    /// The cells that the edit script of every Levenshtein alignment passes
+    /// through.
+    Path = 2,
+    /// How long each phase of comparing files takes.
+    Timing = 4,
+}
+
+static ENABLED: AtomicU8 = AtomicU8::new(0);
+
+/// Traces `trace` for the rest of the process.
+pub fn enable(trace: Trace) {
+    ENABLED.fetch_or(trace as u8, Ordering::Relaxed);
+}
+
+/// Returns whether `trace` is traced.
+pub fn is_enabled(trace: Trace) -> bool {
+    ENABLED.load(Ordering::Relaxed) & trace as u8 != 0
+}
+
+/// Writes a line of the trace.
+pub(crate) fn line(text: &str) {
+    eprintln!("lev-diff: trace: {text}");
+}
+
+/// Writes how long `phase` took since `start` when timing is traced.
+pub fn elapsed(phase: &str, start: Instant) {
+    if is_enabled(Trace::Timing) {
+        line(&format!("timing: {phase} took {:.3?}", start.elapsed()));
+    }
+}
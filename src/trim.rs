@@ -0,0 +1,73 @@
+//! Skipping the common head and tail of two sequences before diffing them.
+//!
+//! Most real file pairs only differ somewhere in the middle, so the
+//! quadratic algorithms only need to see that part.
+
+use crate::hook::DiffHook;
+
+/// Returns the length of the common prefix of `s1` and `s2` and of the
+/// common suffix of what remains after it.
+pub(crate) fn common<T: Eq>(s1: &[T], s2: &[T]) -> (usize, usize) {
+    let prefix = s1.iter().zip(s2).take_while(|(a, b)| a == b).count();
+    let (rest1, rest2) = (&s1[prefix..], &s2[prefix..]);
+    let suffix = rest1
+        .iter()
+        .rev()
+        .zip(rest2.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    (prefix, suffix)
+}
+
+/// Forwards the steps of a diff of the middle parts to the hook of the whole
+/// diff, shifted past the prefix, and holds back `finish`.
+struct Shifted<'a, H: ?Sized> {
+    hook: &'a mut H,
+    old: usize,
+    new: usize,
+}
+
+impl<H: DiffHook + ?Sized> DiffHook for Shifted<'_, H> {
+    fn on_equal(&mut self, old: usize, new: usize) {
+        self.hook.on_equal(self.old + old, self.new + new);
+    }
+
+    fn on_insert(&mut self, old: usize, new: usize) {
+        self.hook.on_insert(self.old + old, self.new + new);
+    }
+
+    fn on_delete(&mut self, old: usize, new: usize) {
+        self.hook.on_delete(self.old + old, self.new + new);
+    }
+
+    fn on_replace(&mut self, old: usize, new: usize) {
+        self.hook.on_replace(self.old + old, self.new + new);
+    }
+}
+
+/// Diffs `s1` and `s2` with `diff`, which only gets them without their
+/// common prefix and suffix. Those are passed to `hook` as equal items.
+pub(crate) fn trimmed<T, H, F>(s1: &[T], s2: &[T], hook: &mut H, diff: F)
+where
+    T: Eq,
+    H: DiffHook + ?Sized,
+    F: FnOnce(&[T], &[T], &mut dyn DiffHook),
+{
+    let (prefix, suffix) = common(s1, s2);
+    for i in 0..prefix {
+        hook.on_equal(i, i);
+    }
+    let (end1, end2) = (s1.len() - suffix, s2.len() - suffix);
+    if prefix < end1 || prefix < end2 {
+        let mut shifted = Shifted {
+            hook: &mut *hook,
+            old: prefix,
+            new: prefix,
+        };
+        diff(&s1[prefix..end1], &s2[prefix..end2], &mut shifted);
+    }
+    for k in 0..suffix {
+        hook.on_equal(end1 + k, end2 + k);
+    }
+    hook.finish();
+}
@@ -0,0 +1,300 @@
+//! Full-screen viewer for a line diff, for diffs too long to read in the
+//! scrollback.
+
+use std::io;
+
+use lev_diff::{Action, Diff};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    DefaultTerminal, Frame,
+};
+
+/// Unchanged lines shown above a hunk when jumping to it.
+const HUNK_CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Context,
+    Remove,
+    Add,
+}
+
+/// A displayed line with its 1-based numbers in the old and new file.
+struct Row {
+    kind: Kind,
+    old: Option<usize>,
+    new: Option<usize>,
+    text: String,
+}
+
+/// Flattens the edit script of `diff` into rows, with the removed lines of a
+/// run of changes before the added ones.
+fn rows(diff: &Diff<&str>) -> Vec<Row> {
+    let mut rows = Vec::with_capacity(diff.actions.len());
+    let mut added = vec![];
+    let row = |kind, old: Option<usize>, new: Option<usize>, text: &str| Row {
+        kind,
+        old: old.map(|i| i + 1),
+        new: new.map(|j| j + 1),
+        text: text.replace('\t', "    "),
+    };
+    for action in diff.actions {
+        match *action {
+            Action::Add(_, j) => added.push(row(Kind::Add, None, Some(j), diff.new[j])),
+            Action::Remove(i, _) => rows.push(row(Kind::Remove, Some(i), None, diff.old[i])),
+            Action::Substitute(i, j) => {
+                rows.push(row(Kind::Remove, Some(i), None, diff.old[i]));
+                added.push(row(Kind::Add, None, Some(j), diff.new[j]));
+            }
+            Action::Ignore(i, j) => {
+                rows.append(&mut added);
+                rows.push(row(Kind::Context, Some(i), Some(j), diff.old[i]));
+            }
+        }
+    }
+    rows.append(&mut added);
+    rows
+}
+
+/// Returns the index of the first row of every run of changes.
+fn hunks(rows: &[Row]) -> Vec<usize> {
+    (0..rows.len())
+        .filter(|&i| rows[i].kind != Kind::Context && (i == 0 || rows[i - 1].kind == Kind::Context))
+        .collect()
+}
+
+struct Viewer<'a> {
+    title: String,
+    old: &'a [&'a str],
+    new: &'a [&'a str],
+    diff: &'a dyn Fn(bool) -> Vec<Action>,
+    ignore_space: bool,
+    rows: Vec<Row>,
+    hunks: Vec<usize>,
+    /// The first visible row.
+    top: usize,
+    /// The number of rows that fit on the screen at the last draw.
+    height: usize,
+    /// The search being typed, if any.
+    input: Option<String>,
+    pattern: String,
+    message: String,
+}
+
+impl Viewer<'_> {
+    fn set_actions(&mut self, actions: &[Action]) {
+        self.rows = rows(&Diff {
+            old: self.old,
+            new: self.new,
+            actions,
+        });
+        self.hunks = hunks(&self.rows);
+        self.top = self.top.min(self.rows.len().saturating_sub(1));
+    }
+
+    fn scroll_to(&mut self, top: usize) {
+        self.top = top.min(self.rows.len().saturating_sub(self.height));
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.scroll_to(self.top.saturating_add_signed(delta));
+    }
+
+    fn next_hunk(&mut self) {
+        match self
+            .hunks
+            .iter()
+            .find(|&&start| start.saturating_sub(HUNK_CONTEXT) > self.top)
+        {
+            Some(&start) => self.scroll_to(start.saturating_sub(HUNK_CONTEXT)),
+            None => self.message = "No next hunk".to_string(),
+        }
+    }
+
+    fn prev_hunk(&mut self) {
+        match self
+            .hunks
+            .iter()
+            .rfind(|&&start| start.saturating_sub(HUNK_CONTEXT) < self.top)
+        {
+            Some(&start) => self.scroll_to(start.saturating_sub(HUNK_CONTEXT)),
+            None => self.message = "No previous hunk".to_string(),
+        }
+    }
+
+    /// Scrolls to the next row containing the pattern, wrapping around.
+    fn search(&mut self) {
+        if self.pattern.is_empty() {
+            return;
+        }
+        let len = self.rows.len();
+        let found = (1..=len)
+            .map(|delta| (self.top + delta) % len)
+            .find(|&i| self.rows[i].text.contains(&self.pattern));
+        match found {
+            Some(i) => self.scroll_to(i),
+            None => self.message = format!("Pattern not found: {}", self.pattern),
+        }
+    }
+
+    fn toggle_space(&mut self) {
+        self.ignore_space = !self.ignore_space;
+        let actions = (self.diff)(self.ignore_space);
+        self.set_actions(&actions);
+        self.scroll_by(0);
+    }
+
+    /// Handles a key press and returns whether to quit.
+    fn key(&mut self, code: KeyCode) -> bool {
+        self.message.clear();
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Enter => {
+                    if !input.is_empty() {
+                        self.pattern = std::mem::take(input);
+                    }
+                    self.input = None;
+                    self.search();
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return false;
+        }
+        let page = self.height.max(1) as isize;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => self.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_by(-1),
+            KeyCode::Char(' ' | 'f') | KeyCode::PageDown => self.scroll_by(page),
+            KeyCode::Char('b') | KeyCode::PageUp => self.scroll_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll_to(0),
+            KeyCode::Char('G') | KeyCode::End => self.scroll_to(usize::MAX),
+            KeyCode::Char('n') => self.next_hunk(),
+            KeyCode::Char('p') => self.prev_hunk(),
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('w') => self.toggle_space(),
+            _ => {}
+        }
+        false
+    }
+
+    /// Splits `text` into spans, highlighting the occurrences of the
+    /// pattern.
+    fn spans<'t>(&self, text: &'t str, style: Style) -> Vec<Span<'t>> {
+        if self.pattern.is_empty() {
+            return vec![Span::styled(text, style)];
+        }
+        let mut spans = vec![];
+        let mut rest = text;
+        while let Some(at) = rest.find(&self.pattern) {
+            let end = at + self.pattern.len();
+            spans.push(Span::styled(&rest[..at], style));
+            spans.push(Span::styled(
+                &rest[at..end],
+                style.add_modifier(Modifier::REVERSED),
+            ));
+            rest = &rest[end..];
+        }
+        spans.push(Span::styled(rest, style));
+        spans
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [body, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        self.height = body.height as usize;
+        self.scroll_by(0);
+        let width = self.rows.len().to_string().len();
+        let number = |n: Option<usize>| match n {
+            Some(n) => format!("{n:>width$}"),
+            None => " ".repeat(width),
+        };
+        let lines = self.rows[self.top..]
+            .iter()
+            .take(self.height)
+            .map(|row| {
+                let (marker, style) = match row.kind {
+                    Kind::Context => (' ', Style::new()),
+                    Kind::Remove => ('-', Style::new().fg(Color::Red)),
+                    Kind::Add => ('+', Style::new().fg(Color::Green)),
+                };
+                let gutter = format!("{} {} {marker} ", number(row.old), number(row.new));
+                let mut spans = vec![Span::styled(gutter, Style::new().fg(Color::DarkGray))];
+                spans.extend(self.spans(&row.text, style));
+                Line::from(spans)
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(lines), body);
+
+        let text =
+            if let Some(input) = &self.input {
+                format!("/{input}")
+            } else if !self.message.is_empty() {
+                self.message.clone()
+            } else {
+                let current = self
+                    .hunks
+                    .iter()
+                    .filter(|&&start| start < self.top + self.height)
+                    .count();
+                format!(
+                "{}  line {}/{}  hunk {current}/{}{}  (n/p hunks, / search, w whitespace, q quit)",
+                self.title,
+                (self.top + 1).min(self.rows.len()),
+                self.rows.len(),
+                self.hunks.len(),
+                if self.ignore_space { "  ignoring whitespace" } else { "" },
+            )
+            };
+        let style = Style::new().add_modifier(Modifier::REVERSED);
+        frame.render_widget(Paragraph::new(text).style(style), status);
+    }
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, viewer: &mut Viewer) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| viewer.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && viewer.key(key.code) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Shows the edit script of `initial` until the user quits.
+///
+/// `diff` recomputes the edit script, ignoring all whitespace when its
+/// argument is set, for when the user toggles that view.
+pub fn run(
+    old_name: &str,
+    new_name: &str,
+    initial: &Diff<&str>,
+    diff: &dyn Fn(bool) -> Vec<Action>,
+) -> io::Result<()> {
+    let mut viewer = Viewer {
+        title: format!("{old_name} → {new_name}"),
+        old: initial.old,
+        new: initial.new,
+        diff,
+        ignore_space: false,
+        rows: vec![],
+        hunks: vec![],
+        top: 0,
+        height: 0,
+        input: None,
+        pattern: String::new(),
+        message: String::new(),
+    };
+    viewer.set_actions(initial.actions);
+    ratatui::run(|terminal| event_loop(terminal, &mut viewer))
+}